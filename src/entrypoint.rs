@@ -1,12 +1,21 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
+    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult,
+    program_error::PrintProgramError, pubkey::Pubkey,
 };
 
+use crate::error::AppError;
+
 entrypoint!(process_instruction);
 fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    crate::processor::process_instruction(program_id, accounts, instruction_data)
+    let result = crate::processor::process_instruction(program_id, accounts, instruction_data);
+    if let Err(error) = result {
+        // logs the AppError message next to the numeric custom code
+        error.print::<AppError>();
+        return Err(error);
+    }
+    Ok(())
 }
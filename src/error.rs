@@ -1,4 +1,9 @@
-use solana_program::program_error::ProgramError;
+use num_derive::FromPrimitive;
+use solana_program::{
+    decode_error::DecodeError,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+};
 use thiserror::Error;
 
 #[macro_export]
@@ -9,7 +14,7 @@ macro_rules! ferror {
     }};
 }
 
-#[derive(Error, Debug, Copy, Clone)]
+#[derive(Error, Debug, Copy, Clone, FromPrimitive)]
 pub enum AppError {
     #[error("Invalid signer")]
     InvalidSigner = 0xfa01,
@@ -41,6 +46,173 @@ pub enum AppError {
     #[error("Checked calculate failed")]
     CheckedCalculateFailed = 0xfa0a,
 
+    #[error("Invalid uri")]
+    InvalidUri = 0xfa0b,
+
+    #[error("Uri too long")]
+    UriTooLong = 0xfa0c,
+
+    #[error("Name too long")]
+    NameTooLong = 0xfa0d,
+
+    #[error("Invalid royalty")]
+    InvalidRoyalty = 0xfa0e,
+
+    #[error("Invalid creator shares")]
+    InvalidCreatorShares = 0xfa0f,
+
+    #[error("Too many creators")]
+    TooManyCreators = 0xfa10,
+
+    #[error("Symbol too long")]
+    SymbolTooLong = 0xfa11,
+
+    #[error("Not token owner")]
+    NotTokenOwner = 0xfa12,
+
+    #[error("Invalid token amount")]
+    InvalidTokenAmount = 0xfa13,
+
+    #[error("Not update authority")]
+    NotUpdateAuthority = 0xfa14,
+
+    #[error("Metadata immutable")]
+    MetadataImmutable = 0xfa15,
+
+    #[error("Batch too large")]
+    BatchTooLarge = 0xfa16,
+
+    #[error("Invalid decimals")]
+    InvalidDecimals = 0xfa17,
+
+    #[error("Royalty without creators")]
+    RoyaltyWithoutCreators = 0xfa18,
+
+    #[error("Duplicate creator")]
+    DuplicateCreator = 0xfa19,
+
+    #[error("Zero share creator")]
+    ZeroShareCreator = 0xfa1a,
+
+    #[error("Supply exhausted")]
+    SupplyExhausted = 0xfa1b,
+
+    #[error("Instruction data too large")]
+    InstructionDataTooLarge = 0xfa1c,
+
+    #[error("Insufficient funds")]
+    InsufficientFunds = 0xfa1d,
+
+    #[error("Invalid authority")]
+    InvalidAuthority = 0xfa1e,
+
+    #[error("State from newer version")]
+    StateFromNewerVersion = 0xfa1f,
+
+    #[error("Not a delegate")]
+    NotADelegate = 0xfa20,
+
+    #[error("Too many delegates")]
+    TooManyDelegates = 0xfa21,
+
+    #[error("Invalid payment mint")]
+    InvalidPaymentMint = 0xfa22,
+
+    #[error("Invalid uri template")]
+    InvalidUriTemplate = 0xfa23,
+
+    #[error("Invalid collection mint")]
+    InvalidCollectionMint = 0xfa24,
+
+    #[error("Not whitelisted")]
+    NotWhitelisted = 0xfa25,
+
+    #[error("Allocation exhausted")]
+    AllocationExhausted = 0xfa26,
+
+    #[error("Collection verify failed")]
+    CollectionVerifyFailed = 0xfa27,
+
+    #[error("Inconsistent mint args")]
+    InconsistentMintArgs = 0xfa28,
+
+    #[error("Mint not live")]
+    MintNotLive = 0xfa29,
+
+    #[error("Mint ended")]
+    MintEnded = 0xfa2a,
+
+    #[error("Invalid mint window")]
+    InvalidMintWindow = 0xfa2b,
+
+    #[error("Wallet limit reached")]
+    WalletLimitReached = 0xfa2c,
+
+    #[error("Invalid slot hashes sysvar")]
+    InvalidSlotHashesSysvar = 0xfa2d,
+
+    #[error("Program paused")]
+    ProgramPaused = 0xfa2e,
+
+    #[error("No lines remaining")]
+    NoLinesRemaining = 0xfa2f,
+
+    #[error("Account already frozen")]
+    AccountAlreadyFrozen = 0xfa30,
+
+    #[error("Account not frozen")]
+    AccountNotFrozen = 0xfa31,
+
+    #[error("Mint account does not match the signed expected mint")]
+    MintKeyMismatch = 0xfa32,
+
+    #[error("Token is not transferable")]
+    NonTransferable = 0xfa33,
+
+    #[error("Numerical overflow")]
+    NumericalOverflow = 0xfa34,
+
+    #[error("Invalid token account")]
+    InvalidTokenAccount = 0xfa35,
+
+    #[error("Token account holds a different mint")]
+    InvalidMint = 0xfa36,
+
+    #[error("Invalid instruction data")]
+    InvalidInstructionData = 0xfa37,
+
+    #[error("Collection is sealed")]
+    CollectionSealed = 0xfa38,
+
+    #[error("Wallet is not on the allowlist")]
+    NotAllowlisted = 0xfa39,
+
+    #[error("Arithmetic overflow")]
+    ArithmeticOverflow = 0xfa3a,
+
+    #[error("Invalid program id")]
+    InvalidProgramId = 0xfa3b,
+
+    #[error("Invalid token metadata program")]
+    InvalidMetadataProgram = 0xfa3c,
+
+    #[error("Token account still holds tokens")]
+    AccountNotEmpty = 0xfa3d,
+
+    #[error("Token-2022 mints are not supported by the token metadata program")]
+    UnsupportedTokenProgram = 0xfa3e,
+
+    #[error("Fee basis points above 10000")]
+    InvalidFeeBps = 0xfa3f,
+
+    #[error("Session expired")]
+    SessionExpired = 0xfa40,
+
+    #[error("Session mint budget used up")]
+    SessionExhausted = 0xfa41,
+
+    #[error("Session revoked or never created")]
+    InvalidSession = 0xfa42,
 }
 
 impl From<AppError> for ProgramError {
@@ -48,3 +220,15 @@ impl From<AppError> for ProgramError {
         ProgramError::Custom(err as u32)
     }
 }
+
+impl<T> DecodeError<T> for AppError {
+    fn type_of() -> &'static str {
+        "AppError"
+    }
+}
+
+impl PrintProgramError for AppError {
+    fn print<E>(&self) {
+        msg!("Error: {}", self);
+    }
+}
@@ -0,0 +1,52 @@
+//! Deterministic hashing for anything that must agree across validators.
+//!
+//! Both helpers are defined by keccak-256 over the concatenation of the given
+//! byte slices, so their outputs stay stable across Rust and dependency versions.
+//! `std`'s `DefaultHasher` gives no such guarantee and must not be used on chain.
+
+use solana_program::keccak;
+
+/// keccak-256 of the concatenated `vals`.
+pub fn keccak256(vals: &[&[u8]]) -> [u8; 32] {
+    keccak::hashv(vals).to_bytes()
+}
+
+/// First 8 bytes of `keccak256(vals)` read as a little-endian u64.
+pub fn short_hash64(vals: &[&[u8]]) -> u64 {
+    let hash = keccak256(vals);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(hash: [u8; 32]) -> String {
+        hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn keccak256_matches_known_answers() {
+        assert_eq!(
+            hex(keccak256(&[])),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex(keccak256(&[b"abc"])),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+
+    #[test]
+    fn keccak256_hashes_the_concatenation() {
+        assert_eq!(keccak256(&[b"a", b"", b"bc"]), keccak256(&[b"abc"]));
+    }
+
+    #[test]
+    fn short_hash64_reads_the_first_bytes_little_endian() {
+        assert_eq!(short_hash64(&[]), 0x3c23_f786_0146_d2c5);
+        assert_eq!(short_hash64(&[b"abc"]), 0x4fa9_45ea_7a65_034e);
+    }
+}
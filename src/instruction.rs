@@ -1,15 +1,1466 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_metadata::{
+    pda::{find_master_edition_account, find_metadata_account},
+    state::{EDITION, EDITION_MARKER_BIT_SIZE, PREFIX},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address;
 
+use crate::state::{
+    CONFIG_LINES_SEED, CONFIG_SEED, MINT_AUTHORITY_SEED, RECORD_SEED, SESSION_SEED,
+    WALLET_COUNTER_SEED, WHITELIST_SEED,
+};
+
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct CreatorArgs {
+    pub address: Pubkey,
+    /// percentage of the royalty, all shares sum to 100
+    pub share: u8,
+    /// listed for provenance only, the only kind of creator allowed a zero share
+    pub provenance_only: bool,
+}
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct MintNftArgs {
     pub uri: String,
-    pub title:  Option<String>
+    pub title:  Option<String>,
+    pub symbol: Option<String>,
+    pub seller_fee_basis_points: u16,
+    /// the config's default creators when None, or the signer alone at 100%
+    /// if the config has none
+    pub creators: Option<Vec<CreatorArgs>>,
+    /// whether UpdateMetadata may change the metadata later
+    pub mutable: bool,
+    /// print editions allowed from the master edition: Some(0) for a true 1/1
+    /// with no prints, Some(n) for at most n prints, None for unlimited prints
+    pub max_supply: Option<u64>,
+    /// 0 for NFTs, up to 9 for fungible or semi-fungible drops
+    pub decimals: u8,
+    /// tokens minted to the signer, a master edition is only created for 1 token with 0 decimals
+    pub amount: u64,
+    /// set the mint address as return data, only honoured by a single Mint
+    pub return_mint: bool,
+    /// collection the NFT claims to belong to, unverified until a
+    /// SetCollection call by the collection authority
+    pub collection_mint: Option<Pubkey>,
+    /// reject argument combinations that would otherwise be silently ignored
+    pub strict: bool,
+    /// create the NFT as a sized collection parent that other NFTs can join
+    pub sized_collection: bool,
+    /// freeze the token account right after minting, until a Thaw
+    pub soulbound: bool,
+    /// mint account the client generated and signed with, checked against
+    /// the passed mint account before anything is created
+    pub expected_mint: Pubkey,
+    /// wallet receiving the NFT, the signer when None; the signer pays either way
+    pub recipient: Option<Pubkey>,
+    /// option count of each randomized trait, up to 8; the drawn indices fill
+    /// the `{traits}` placeholder of the uri and are returned as return data
+    pub trait_options: Vec<u8>,
+    /// metadata update authority, the signer when None
+    pub update_authority: Option<Pubkey>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct UpdateMetadataArgs {
+    pub new_uri: Option<String>,
+    pub new_name: Option<String>,
+    pub new_seller_fee_basis_points: Option<u16>,
+    /// replaces the creator list, keeping the verified flag of creators that
+    /// stay; an empty list removes all creators
+    pub new_creators: Option<Vec<CreatorArgs>>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct InitializeConfigArgs {
+    pub max_supply: u64,
+    pub price_lamports: u64,
+    pub treasury: Pubkey,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct InitializeCollectionArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Fields left as None keep their current value.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Default)]
+pub struct UpdateConfigArgs {
+    pub price_lamports: Option<u64>,
+    pub treasury: Option<Pubkey>,
+    pub is_paused: Option<bool>,
+    pub mint_start: Option<u64>,
+    pub mint_end: Option<u64>,
+    pub randomized: Option<bool>,
+    pub protocol_fee_bps: Option<u16>,
+    pub fee_recipient: Option<Pubkey>,
+    /// an empty list clears the defaults
+    pub default_creators: Option<Vec<CreatorArgs>>,
 }
 
 #[repr(C)]
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
 pub enum GameInstruction {
     Mint(MintNftArgs),
+    Burn,
+    UpdateMetadata(UpdateMetadataArgs),
+    BatchMint(Vec<MintNftArgs>),
+    BatchCloseAccounts,
+    InitializeConfig(InitializeConfigArgs),
+    Transfer { amount: u64 },
+    SetPrice { price_lamports: u64 },
+    AddDelegate { delegate: Pubkey },
+    RemoveDelegate { delegate: Pubkey },
+    SetTokenPrice { payment_mint: Option<Pubkey>, price_tokens: u64 },
+    SetCollection,
+    SetUriTemplate { uri_template: Option<String> },
+    AddToWhitelist { wallet: Pubkey, allocation: u64 },
+    RemoveFromWhitelist { wallet: Pubkey },
+    SetWhitelistEnabled { enabled: bool },
+    /// summarizes up to 10 accounts into return data, matching them against
+    /// the PDAs derivable from the given context
+    DebugDump { authority: Option<Pubkey>, wallet: Option<Pubkey>, mint: Option<Pubkey> },
+    VerifyCreator,
+    VerifyCollection,
+    SetMintWindow { start: u64, end: u64 },
+    SetMaxPerWallet { max_per_wallet: u8 },
+    UpdateConfig(UpdateConfigArgs),
+    /// appends (name, uri) pairs to the pool randomized mints draw from
+    AddConfigLines { lines: Vec<(String, String)> },
+    /// Mint for allowlisted wallets. The leaf is `sha256(signer pubkey)` and
+    /// the proof lists sibling hashes from the leaf up, see verify_merkle_proof.
+    MintWithProof(MintNftArgs, Vec<[u8; 32]>),
+    UpdateMerkleRoot { new_root: [u8; 32] },
+    Freeze,
+    Thaw,
+    PrintEdition { edition_number: u64 },
+    InitializeCollection(InitializeCollectionArgs),
+    TransferUpdateAuthority { new_authority: Pubkey },
+    TransferNft,
+    SetPrimarySaleHappened,
+    SealCollection,
+    /// approves `delegate` for the signer's NFT, e.g. before a marketplace listing
+    Delegate { delegate: Pubkey },
+    RevokeDelegate,
+    /// config authority mints `args` to each of `recipients_count` wallets,
+    /// free of charge but within max_supply
+    Airdrop { recipients_count: u8, args: MintNftArgs },
+    UpdateUri { new_uri: String },
+    CloseTokenAccount,
+    /// reports the supported state version and the highest one among the
+    /// passed state accounts as return data, see VersionReport
+    GetVersion,
+    /// lets `session_key` sign Mint for the signing player until the unix
+    /// timestamp `expiry`, for at most `max_mints` mints; an existing session
+    /// of the same key is replaced
+    CreateSession { session_key: Pubkey, expiry: u64, max_mints: u64 },
+    RevokeSession { session_key: Pubkey },
+}
+
+fn config_address(program_id: &Pubkey, config_authority: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_SEED, config_authority.as_ref()], program_id).0
+}
+
+fn mint_authority_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[MINT_AUTHORITY_SEED, program_id.as_ref()], program_id).0
+}
+
+fn record_address(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[RECORD_SEED, mint.as_ref()], program_id).0
+}
+
+/// Fills an optional account slot with the "absent" sentinel that
+/// `utils::optional_account` skips.
+fn absent_account(program_id: &Pubkey) -> AccountMeta {
+    AccountMeta::new_readonly(*program_id, false)
+}
+
+/// A writable optional slot, absent for None.
+fn optional_account(program_id: &Pubkey, key: Option<Pubkey>) -> AccountMeta {
+    key.map_or_else(|| absent_account(program_id), |key| AccountMeta::new(key, false))
+}
+
+/// The mint record and config an NFT is checked against for a seal, see
+/// `processor::assert_not_sealed`. NFTs not minted by this program have no
+/// record, their config slot is left absent.
+fn seal_accounts(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    config_authority: Option<&Pubkey>,
+) -> [AccountMeta; 2] {
+    [
+        AccountMeta::new_readonly(record_address(program_id, mint), false),
+        match config_authority {
+            Some(config_authority) => {
+                AccountMeta::new_readonly(config_address(program_id, config_authority), false)
+            }
+            None => absent_account(program_id),
+        },
+    ]
+}
+
+/// Authority-signed instruction that only touches the config.
+fn config_instruction(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    instruction: &GameInstruction,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        instruction,
+        vec![
+            AccountMeta::new_readonly(*config_authority, true),
+            AccountMeta::new(config_address(program_id, config_authority), false),
+        ],
+    )
+}
+
+/// Optional slots of a Mint, MintWithProof or BatchMint; what the config
+/// doesn't need is left absent.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OptionalMintAccounts {
+    /// payment mint of a token-priced config, paid from the payer's to the
+    /// treasury's associated token account
+    pub payment_mint: Option<Pubkey>,
+    /// the minter's WhitelistEntry, for configs with the whitelist enabled
+    pub whitelist: bool,
+    /// the minter's WalletCounter, for configs with a max_per_wallet
+    pub wallet_counter: bool,
+    /// the ConfigLines of a randomized config
+    pub config_lines: bool,
+    /// the SlotHashes sysvar, for randomized configs and trait draws
+    pub slot_hashes: bool,
+    /// collection NFT of a config with a collection
+    pub collection_mint: Option<Pubkey>,
+    /// fee recipient of a config with a protocol fee
+    pub fee_recipient: Option<Pubkey>,
+    /// the payer is a session key minting for `args.recipient`, its player
+    pub session: bool,
+}
+
+impl OptionalMintAccounts {
+    fn payment(&self, program_id: &Pubkey, payer: &Pubkey, treasury: &Pubkey) -> Vec<AccountMeta> {
+        match self.payment_mint {
+            Some(payment_mint) => vec![
+                AccountMeta::new_readonly(payment_mint, false),
+                AccountMeta::new(get_associated_token_address(payer, &payment_mint), false),
+                AccountMeta::new(get_associated_token_address(treasury, &payment_mint), false),
+            ],
+            None => vec![absent_account(program_id); 3],
+        }
+    }
+
+    /// Whitelist, wallet counter, config lines and slot hashes slots, derived
+    /// from the config and the wallet the mint counts against.
+    fn limits(&self, program_id: &Pubkey, config: &Pubkey, minter: &Pubkey) -> Vec<AccountMeta> {
+        let derive = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, program_id).0;
+        vec![
+            optional_account(
+                program_id,
+                self.whitelist
+                    .then(|| derive(&[WHITELIST_SEED, config.as_ref(), minter.as_ref()])),
+            ),
+            optional_account(
+                program_id,
+                self.wallet_counter
+                    .then(|| derive(&[WALLET_COUNTER_SEED, config.as_ref(), minter.as_ref()])),
+            ),
+            optional_account(
+                program_id,
+                self.config_lines.then(|| derive(&[CONFIG_LINES_SEED, config.as_ref()])),
+            ),
+            match self.slot_hashes {
+                true => AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+                false => absent_account(program_id),
+            },
+        ]
+    }
+
+    fn collection(&self, program_id: &Pubkey) -> Vec<AccountMeta> {
+        collection_accounts(program_id, self.collection_mint.as_ref())
+    }
+}
+
+/// Collection mint, metadata and master edition slots, absent for None.
+fn collection_accounts(program_id: &Pubkey, collection_mint: Option<&Pubkey>) -> Vec<AccountMeta> {
+    match collection_mint {
+        Some(collection_mint) => vec![
+            AccountMeta::new_readonly(*collection_mint, false),
+            AccountMeta::new(find_metadata_account(collection_mint).0, false),
+            AccountMeta::new_readonly(find_master_edition_account(collection_mint).0, false),
+        ],
+        None => vec![absent_account(program_id); 3],
+    }
+}
+
+/// Programs and sysvars every mint creates its accounts with, after the
+/// mint authority and the payer.
+fn mint_program_accounts() -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(mpl_token_metadata::id(), false),
+    ]
+}
+
+pub fn initialize_config(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    args: InitializeConfigArgs,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::InitializeConfig(args),
+        vec![
+            AccountMeta::new(*config_authority, true),
+            AccountMeta::new(config_address(program_id, config_authority), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Mints one NFT of the config owned by `config_authority` to `payer`, or to
+/// `args.recipient` when set. `mint` is a fresh keypair that signs next to
+/// the payer, it is also written to `args.expected_mint`.
+pub fn mint_nft(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    config_authority: &Pubkey,
+    treasury: &Pubkey,
+    args: MintNftArgs,
+    optional: &OptionalMintAccounts,
+) -> Instruction {
+    mint_instruction(program_id, payer, mint, config_authority, treasury, args, None, optional)
+}
+
+/// Mint of an allowlisted payer, see `mint_nft`.
+#[allow(clippy::too_many_arguments)]
+pub fn mint_with_proof(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    config_authority: &Pubkey,
+    treasury: &Pubkey,
+    args: MintNftArgs,
+    proof: Vec<[u8; 32]>,
+    optional: &OptionalMintAccounts,
+) -> Instruction {
+    let proof = Some(proof);
+    mint_instruction(program_id, payer, mint, config_authority, treasury, args, proof, optional)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn mint_instruction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    config_authority: &Pubkey,
+    treasury: &Pubkey,
+    mut args: MintNftArgs,
+    proof: Option<Vec<[u8; 32]>>,
+    optional: &OptionalMintAccounts,
+) -> Instruction {
+    args.expected_mint = *mint;
+    let owner = args.recipient.unwrap_or(*payer);
+    // a session mint counts against its player
+    let minter = if optional.session { owner } else { *payer };
+    let config = config_address(program_id, config_authority);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(mint_authority_address(program_id), false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*mint, true),
+        AccountMeta::new(get_associated_token_address(&owner, mint), false),
+    ];
+    accounts.extend(mint_program_accounts());
+    accounts.extend([
+        AccountMeta::new(find_metadata_account(mint).0, false),
+        AccountMeta::new(find_master_edition_account(mint).0, false),
+        AccountMeta::new(record_address(program_id, mint), false),
+        AccountMeta::new(config, false),
+        AccountMeta::new(*treasury, false),
+    ]);
+    accounts.extend(optional.payment(program_id, payer, treasury));
+    accounts.extend(optional.limits(program_id, &config, &minter));
+    accounts.push(match args.recipient {
+        Some(recipient) => AccountMeta::new_readonly(recipient, false),
+        None => absent_account(program_id),
+    });
+    accounts.extend(optional.collection(program_id));
+    accounts.push(optional_account(program_id, optional.fee_recipient));
+    accounts.push(optional_account(
+        program_id,
+        optional.session.then(|| {
+            Pubkey::find_program_address(
+                &[SESSION_SEED, owner.as_ref(), payer.as_ref()],
+                program_id,
+            )
+            .0
+        }),
+    ));
+    let instruction = match proof {
+        Some(proof) => GameInstruction::MintWithProof(args, proof),
+        None => GameInstruction::Mint(args),
+    };
+    Instruction::new_with_borsh(*program_id, &instruction, accounts)
+}
+
+/// Burns the owner's NFT. `collection_mint` is required once the NFT is
+/// verified into a collection, whose metadata then shrinks by one.
+pub fn burn(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    collection_mint: Option<&Pubkey>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::Burn,
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new(find_metadata_account(mint).0, false),
+            AccountMeta::new(find_master_edition_account(mint).0, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(mpl_token_metadata::id(), false),
+            match collection_mint {
+                Some(collection_mint) => {
+                    AccountMeta::new(find_metadata_account(collection_mint).0, false)
+                }
+                None => absent_account(program_id),
+            },
+        ],
+    )
+}
+
+/// Metadata update of `mint` signed by its update authority.
+/// `config_authority` owns the config the NFT was minted under, None for
+/// NFTs this program didn't mint.
+pub fn update_metadata(
+    program_id: &Pubkey,
+    update_authority: &Pubkey,
+    mint: &Pubkey,
+    config_authority: Option<&Pubkey>,
+    args: UpdateMetadataArgs,
+) -> Instruction {
+    metadata_instruction(
+        program_id,
+        update_authority,
+        mint,
+        config_authority,
+        &GameInstruction::UpdateMetadata(args),
+    )
+}
+
+pub fn transfer_update_authority(
+    program_id: &Pubkey,
+    update_authority: &Pubkey,
+    mint: &Pubkey,
+    config_authority: Option<&Pubkey>,
+    new_authority: Pubkey,
+) -> Instruction {
+    metadata_instruction(
+        program_id,
+        update_authority,
+        mint,
+        config_authority,
+        &GameInstruction::TransferUpdateAuthority { new_authority },
+    )
+}
+
+pub fn update_uri(
+    program_id: &Pubkey,
+    update_authority: &Pubkey,
+    mint: &Pubkey,
+    config_authority: Option<&Pubkey>,
+    new_uri: String,
+) -> Instruction {
+    metadata_instruction(
+        program_id,
+        update_authority,
+        mint,
+        config_authority,
+        &GameInstruction::UpdateUri { new_uri },
+    )
+}
+
+/// Instruction of the metadata update authority, or a creator for
+/// VerifyCreator, on the metadata of `mint`.
+fn metadata_instruction(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    mint: &Pubkey,
+    config_authority: Option<&Pubkey>,
+    instruction: &GameInstruction,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*signer, true),
+        AccountMeta::new(find_metadata_account(mint).0, false),
+        AccountMeta::new_readonly(mpl_token_metadata::id(), false),
+    ];
+    accounts.extend(seal_accounts(program_id, mint, config_authority));
+    Instruction::new_with_borsh(*program_id, instruction, accounts)
+}
+
+/// Mints one NFT from `args` per item to the payer. Each mint is a fresh
+/// keypair that signs next to the payer and is written to its args.
+pub fn batch_mint(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    config_authority: &Pubkey,
+    treasury: &Pubkey,
+    items: Vec<(Pubkey, MintNftArgs)>,
+    optional: &OptionalMintAccounts,
+) -> Instruction {
+    let config = config_address(program_id, config_authority);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(mint_authority_address(program_id), false),
+        AccountMeta::new(*payer, true),
+    ];
+    accounts.extend(mint_program_accounts());
+    accounts.extend([AccountMeta::new(config, false), AccountMeta::new(*treasury, false)]);
+    accounts.extend(optional.payment(program_id, payer, treasury));
+    accounts.extend(optional.limits(program_id, &config, payer));
+    accounts.extend(optional.collection(program_id));
+    accounts.push(optional_account(program_id, optional.fee_recipient));
+    let mut batch = Vec::with_capacity(items.len());
+    for (mint, mut args) in items {
+        args.expected_mint = mint;
+        accounts.extend([
+            AccountMeta::new(mint, true),
+            AccountMeta::new(get_associated_token_address(payer, &mint), false),
+            AccountMeta::new(find_metadata_account(&mint).0, false),
+            AccountMeta::new(find_master_edition_account(&mint).0, false),
+            AccountMeta::new(record_address(program_id, &mint), false),
+        ]);
+        batch.push(args);
+    }
+    Instruction::new_with_borsh(*program_id, &GameInstruction::BatchMint(batch), accounts)
+}
+
+/// Closes the owner's empty token accounts, skipping those holding tokens.
+pub fn batch_close_accounts(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    token_accounts: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(token_accounts.iter().map(|key| AccountMeta::new(*key, false)));
+    Instruction::new_with_borsh(*program_id, &GameInstruction::BatchCloseAccounts, accounts)
+}
+
+pub fn transfer(
+    program_id: &Pubkey,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::Transfer { amount },
+        vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+pub fn set_price(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    price_lamports: u64,
+) -> Instruction {
+    config_instruction(
+        program_id,
+        config_authority,
+        &GameInstruction::SetPrice { price_lamports },
+    )
+}
+
+pub fn add_delegate(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    delegate: Pubkey,
+) -> Instruction {
+    config_instruction(program_id, config_authority, &GameInstruction::AddDelegate { delegate })
+}
+
+pub fn remove_delegate(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    delegate: Pubkey,
+) -> Instruction {
+    config_instruction(program_id, config_authority, &GameInstruction::RemoveDelegate { delegate })
+}
+
+pub fn set_token_price(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    payment_mint: Option<Pubkey>,
+    price_tokens: u64,
+) -> Instruction {
+    config_instruction(
+        program_id,
+        config_authority,
+        &GameInstruction::SetTokenPrice { payment_mint, price_tokens },
+    )
+}
+
+/// Sets and verifies the collection of `mint`, signed by the collection
+/// authority, which is also the NFT's update authority.
+pub fn set_collection(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    collection_mint: &Pubkey,
+    collection_authority: &Pubkey,
+    config_authority: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(find_metadata_account(mint).0, false)];
+    accounts.extend(collection_accounts(program_id, Some(collection_mint)));
+    accounts.extend([
+        AccountMeta::new(*collection_authority, true),
+        AccountMeta::new_readonly(mpl_token_metadata::id(), false),
+    ]);
+    accounts.extend(seal_accounts(program_id, mint, config_authority));
+    Instruction::new_with_borsh(*program_id, &GameInstruction::SetCollection, accounts)
+}
+
+pub fn set_uri_template(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    uri_template: Option<String>,
+) -> Instruction {
+    config_instruction(
+        program_id,
+        config_authority,
+        &GameInstruction::SetUriTemplate { uri_template },
+    )
+}
+
+fn whitelist_address(program_id: &Pubkey, config_authority: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    let config = config_address(program_id, config_authority);
+    Pubkey::find_program_address(&[WHITELIST_SEED, config.as_ref(), wallet.as_ref()], program_id).0
+}
+
+pub fn add_to_whitelist(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    wallet: Pubkey,
+    allocation: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::AddToWhitelist { wallet, allocation },
+        vec![
+            AccountMeta::new(*config_authority, true),
+            AccountMeta::new_readonly(config_address(program_id, config_authority), false),
+            AccountMeta::new(whitelist_address(program_id, config_authority, &wallet), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn remove_from_whitelist(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    wallet: Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::RemoveFromWhitelist { wallet },
+        vec![
+            AccountMeta::new(*config_authority, true),
+            AccountMeta::new_readonly(config_address(program_id, config_authority), false),
+            AccountMeta::new(whitelist_address(program_id, config_authority, &wallet), false),
+        ],
+    )
+}
+
+pub fn set_whitelist_enabled(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    config_instruction(
+        program_id,
+        config_authority,
+        &GameInstruction::SetWhitelistEnabled { enabled },
+    )
+}
+
+/// Summarizes `accounts`, at most `MAX_DUMP_ACCOUNTS`, into return data.
+pub fn debug_dump(
+    program_id: &Pubkey,
+    accounts: &[Pubkey],
+    authority: Option<Pubkey>,
+    wallet: Option<Pubkey>,
+    mint: Option<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::DebugDump { authority, wallet, mint },
+        accounts
+            .iter()
+            .map(|key| AccountMeta::new_readonly(*key, false))
+            .collect(),
+    )
+}
+
+pub fn verify_creator(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    mint: &Pubkey,
+    config_authority: Option<&Pubkey>,
+) -> Instruction {
+    metadata_instruction(
+        program_id,
+        creator,
+        mint,
+        config_authority,
+        &GameInstruction::VerifyCreator,
+    )
+}
+
+/// Verifies `mint` into the collection it already claims, signed by the
+/// collection authority.
+pub fn verify_collection(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    collection_mint: &Pubkey,
+    collection_authority: &Pubkey,
+    config_authority: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(find_metadata_account(mint).0, false),
+        AccountMeta::new(*collection_authority, true),
+    ];
+    accounts.extend(collection_accounts(program_id, Some(collection_mint)));
+    accounts.push(AccountMeta::new_readonly(mpl_token_metadata::id(), false));
+    accounts.extend(seal_accounts(program_id, mint, config_authority));
+    Instruction::new_with_borsh(*program_id, &GameInstruction::VerifyCollection, accounts)
+}
+
+pub fn set_mint_window(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    start: u64,
+    end: u64,
+) -> Instruction {
+    config_instruction(program_id, config_authority, &GameInstruction::SetMintWindow { start, end })
+}
+
+pub fn set_max_per_wallet(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    max_per_wallet: u8,
+) -> Instruction {
+    config_instruction(
+        program_id,
+        config_authority,
+        &GameInstruction::SetMaxPerWallet { max_per_wallet },
+    )
+}
+
+pub fn update_config(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    args: UpdateConfigArgs,
+) -> Instruction {
+    config_instruction(program_id, config_authority, &GameInstruction::UpdateConfig(args))
+}
+
+pub fn add_config_lines(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    lines: Vec<(String, String)>,
+) -> Instruction {
+    let config = config_address(program_id, config_authority);
+    let lines_address =
+        Pubkey::find_program_address(&[CONFIG_LINES_SEED, config.as_ref()], program_id).0;
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::AddConfigLines { lines },
+        vec![
+            AccountMeta::new(*config_authority, true),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(lines_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn update_merkle_root(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    new_root: [u8; 32],
+) -> Instruction {
+    config_instruction(
+        program_id,
+        config_authority,
+        &GameInstruction::UpdateMerkleRoot { new_root },
+    )
+}
+
+/// Freezes the token account `owner` holds `mint` in, see `thaw`.
+pub fn freeze(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    freeze_instruction(program_id, config_authority, mint, owner, &GameInstruction::Freeze)
+}
+
+pub fn thaw(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    freeze_instruction(program_id, config_authority, mint, owner, &GameInstruction::Thaw)
+}
+
+fn freeze_instruction(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    instruction: &GameInstruction,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        instruction,
+        vec![
+            AccountMeta::new_readonly(*config_authority, true),
+            AccountMeta::new_readonly(config_address(program_id, config_authority), false),
+            AccountMeta::new_readonly(mint_authority_address(program_id), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(record_address(program_id, mint), false),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+/// Prints edition `edition_number` of the master edition of `master_mint`,
+/// held by `payer`, into the fresh `mint` keypair.
+pub fn print_edition(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    master_mint: &Pubkey,
+    mint: &Pubkey,
+    config_authority: Option<&Pubkey>,
+    edition_number: u64,
+) -> Instruction {
+    let metadata_program_id = mpl_token_metadata::id();
+    let mark = (edition_number / EDITION_MARKER_BIT_SIZE).to_string();
+    let edition_mark = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            metadata_program_id.as_ref(),
+            master_mint.as_ref(),
+            EDITION.as_bytes(),
+            mark.as_bytes(),
+        ],
+        &metadata_program_id,
+    )
+    .0;
+    let mut accounts = vec![
+        AccountMeta::new_readonly(mint_authority_address(program_id), false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(*master_mint, false),
+        AccountMeta::new_readonly(find_metadata_account(master_mint).0, false),
+        AccountMeta::new(find_master_edition_account(master_mint).0, false),
+        AccountMeta::new_readonly(get_associated_token_address(payer, master_mint), false),
+        AccountMeta::new(*mint, true),
+        AccountMeta::new(get_associated_token_address(payer, mint), false),
+        AccountMeta::new(find_metadata_account(mint).0, false),
+        AccountMeta::new(find_master_edition_account(mint).0, false),
+        AccountMeta::new(edition_mark, false),
+    ];
+    accounts.extend(mint_program_accounts());
+    accounts.extend(seal_accounts(program_id, master_mint, config_authority));
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::PrintEdition { edition_number },
+        accounts,
+    )
+}
+
+/// Mints the collection NFT of the config into the fresh `mint` keypair.
+pub fn initialize_collection(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    mint: &Pubkey,
+    args: InitializeCollectionArgs,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*config_authority, true),
+        AccountMeta::new(config_address(program_id, config_authority), false),
+        AccountMeta::new_readonly(mint_authority_address(program_id), false),
+        AccountMeta::new(*mint, true),
+        AccountMeta::new(get_associated_token_address(config_authority, mint), false),
+        AccountMeta::new(find_metadata_account(mint).0, false),
+        AccountMeta::new(find_master_edition_account(mint).0, false),
+    ];
+    accounts.extend(mint_program_accounts());
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::InitializeCollection(args),
+        accounts,
+    )
+}
+
+pub fn transfer_nft(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    destination_wallet: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::TransferNft,
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(record_address(program_id, mint), false),
+            AccountMeta::new_readonly(*destination_wallet, false),
+            AccountMeta::new(get_associated_token_address(destination_wallet, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn set_primary_sale_happened(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    config_authority: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(get_associated_token_address(owner, mint), false),
+        AccountMeta::new(find_metadata_account(mint).0, false),
+        AccountMeta::new_readonly(mpl_token_metadata::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(seal_accounts(program_id, mint, config_authority));
+    Instruction::new_with_borsh(*program_id, &GameInstruction::SetPrimarySaleHappened, accounts)
+}
+
+/// Seals the config. A config with a collection passes `collection_mint`,
+/// whose metadata is made immutable.
+pub fn seal_collection(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    collection_mint: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*config_authority, true),
+        AccountMeta::new(config_address(program_id, config_authority), false),
+    ];
+    accounts.extend(match collection_mint {
+        Some(collection_mint) => vec![
+            AccountMeta::new_readonly(mint_authority_address(program_id), false),
+            AccountMeta::new(find_metadata_account(collection_mint).0, false),
+            AccountMeta::new_readonly(mpl_token_metadata::id(), false),
+        ],
+        None => vec![absent_account(program_id); 3],
+    });
+    Instruction::new_with_borsh(*program_id, &GameInstruction::SealCollection, accounts)
+}
+
+pub fn delegate(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    delegate: Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::Delegate { delegate },
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(delegate, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+pub fn revoke_delegate(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::RevokeDelegate,
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+/// Mints one NFT from `args` to each (wallet, mint) of `recipients`, every
+/// mint a fresh keypair that signs next to the config authority. A
+/// randomized config passes `randomized` for its config lines.
+pub fn airdrop(
+    program_id: &Pubkey,
+    config_authority: &Pubkey,
+    recipients: &[(Pubkey, Pubkey)],
+    args: MintNftArgs,
+    collection_mint: Option<&Pubkey>,
+    randomized: bool,
+) -> Instruction {
+    let config = config_address(program_id, config_authority);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(mint_authority_address(program_id), false),
+        AccountMeta::new(*config_authority, true),
+    ];
+    accounts.extend(mint_program_accounts());
+    accounts.push(AccountMeta::new(config, false));
+    accounts.extend(collection_accounts(program_id, collection_mint));
+    let lines = Pubkey::find_program_address(&[CONFIG_LINES_SEED, config.as_ref()], program_id).0;
+    accounts.push(optional_account(program_id, randomized.then_some(lines)));
+    accounts.push(match randomized || !args.trait_options.is_empty() {
+        true => AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        false => absent_account(program_id),
+    });
+    for (wallet, mint) in recipients {
+        accounts.extend([
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new(*mint, true),
+            AccountMeta::new(get_associated_token_address(wallet, mint), false),
+            AccountMeta::new(find_metadata_account(mint).0, false),
+            AccountMeta::new(find_master_edition_account(mint).0, false),
+            AccountMeta::new(record_address(program_id, mint), false),
+        ]);
+    }
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::Airdrop {
+            recipients_count: recipients.len() as u8,
+            args,
+        },
+        accounts,
+    )
+}
+
+/// Closes the owner's empty `token_account` into `destination`.
+pub fn close_token_account(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    token_account: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::CloseTokenAccount,
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+/// Reports the supported state version and the highest among `state_accounts`.
+pub fn get_version(program_id: &Pubkey, state_accounts: &[Pubkey]) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::GetVersion,
+        state_accounts
+            .iter()
+            .map(|key| AccountMeta::new_readonly(*key, false))
+            .collect(),
+    )
+}
+
+fn session_address(program_id: &Pubkey, player: &Pubkey, session_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[SESSION_SEED, player.as_ref(), session_key.as_ref()], program_id)
+        .0
+}
+
+pub fn create_session(
+    program_id: &Pubkey,
+    player: &Pubkey,
+    session_key: Pubkey,
+    expiry: u64,
+    max_mints: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::CreateSession { session_key, expiry, max_mints },
+        vec![
+            AccountMeta::new(*player, true),
+            AccountMeta::new(session_address(program_id, player, &session_key), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn revoke_session(program_id: &Pubkey, player: &Pubkey, session_key: Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &GameInstruction::RevokeSession { session_key },
+        vec![
+            AccountMeta::new(*player, true),
+            AccountMeta::new(session_address(program_id, player, &session_key), false),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        processor::process_instruction,
+        state::{Session, STATE_VERSION},
+        test_utils::*,
+    };
+    use solana_program::entrypoint::ProgramResult;
+
+    fn decoded(instruction: &Instruction) -> GameInstruction {
+        GameInstruction::try_from_slice(&instruction.data).unwrap()
+    }
+
+    /// Runs `instruction` against the fixture of every account it names,
+    /// looked up by key and flagged the way the instruction passes it.
+    fn run(
+        instruction: &Instruction,
+        fixtures: &[TestAccount],
+    ) -> (ProgramResult, Vec<TestAccount>) {
+        let mut accounts: Vec<TestAccount> = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let mut account = fixtures
+                    .iter()
+                    .find(|account| account.key == meta.pubkey)
+                    .unwrap_or_else(|| panic!("no fixture for {}", meta.pubkey))
+                    .clone();
+                account.is_signer = meta.is_signer;
+                account.is_writable = meta.is_writable;
+                account
+            })
+            .collect();
+        let result = process_instruction(&crate::id(), &infos(&mut accounts), &instruction.data);
+        (result, accounts)
+    }
+
+    #[test]
+    fn builders_encode_their_instruction() {
+        let program_id = crate::id();
+        let (wallet, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let other = Pubkey::new_unique();
+        let optional = OptionalMintAccounts::default();
+        let mut args = test_mint_args(mint);
+        let update_args = UpdateMetadataArgs {
+            new_uri: Some(String::from("https://example.com/1.json")),
+            new_name: None,
+            new_seller_fee_basis_points: None,
+            new_creators: None,
+        };
+        let config_args = InitializeConfigArgs {
+            max_supply: 10,
+            price_lamports: 5,
+            treasury: other,
+        };
+        let collection_args = InitializeCollectionArgs {
+            name: String::from("Squirrels"),
+            symbol: String::from("SQRL"),
+            uri: String::from("https://example.com/collection.json"),
+        };
+        let cases = [
+            (
+                mint_nft(&program_id, &wallet, &mint, &other, &other, args.clone(), &optional),
+                GameInstruction::Mint(args.clone()),
+            ),
+            (
+                mint_with_proof(
+                    &program_id,
+                    &wallet,
+                    &mint,
+                    &other,
+                    &other,
+                    args.clone(),
+                    vec![[1; 32]],
+                    &optional,
+                ),
+                GameInstruction::MintWithProof(args.clone(), vec![[1; 32]]),
+            ),
+            (
+                batch_mint(
+                    &program_id,
+                    &wallet,
+                    &other,
+                    &other,
+                    vec![(mint, args.clone())],
+                    &optional,
+                ),
+                GameInstruction::BatchMint(vec![args.clone()]),
+            ),
+            (burn(&program_id, &wallet, &mint, None), GameInstruction::Burn),
+            (
+                update_metadata(&program_id, &wallet, &mint, Some(&other), update_args.clone()),
+                GameInstruction::UpdateMetadata(update_args),
+            ),
+            (
+                transfer_update_authority(&program_id, &wallet, &mint, None, other),
+                GameInstruction::TransferUpdateAuthority { new_authority: other },
+            ),
+            (
+                update_uri(&program_id, &wallet, &mint, None, String::from("uri")),
+                GameInstruction::UpdateUri { new_uri: String::from("uri") },
+            ),
+            (
+                batch_close_accounts(&program_id, &wallet, &[other]),
+                GameInstruction::BatchCloseAccounts,
+            ),
+            (
+                initialize_config(&program_id, &wallet, config_args.clone()),
+                GameInstruction::InitializeConfig(config_args),
+            ),
+            (
+                transfer(&program_id, &other, &mint, &other, &wallet, 3),
+                GameInstruction::Transfer { amount: 3 },
+            ),
+            (
+                set_price(&program_id, &wallet, 7),
+                GameInstruction::SetPrice { price_lamports: 7 },
+            ),
+            (
+                add_delegate(&program_id, &wallet, other),
+                GameInstruction::AddDelegate { delegate: other },
+            ),
+            (
+                remove_delegate(&program_id, &wallet, other),
+                GameInstruction::RemoveDelegate { delegate: other },
+            ),
+            (
+                set_token_price(&program_id, &wallet, Some(mint), 9),
+                GameInstruction::SetTokenPrice { payment_mint: Some(mint), price_tokens: 9 },
+            ),
+            (
+                set_collection(&program_id, &mint, &other, &wallet, None),
+                GameInstruction::SetCollection,
+            ),
+            (
+                set_uri_template(&program_id, &wallet, None),
+                GameInstruction::SetUriTemplate { uri_template: None },
+            ),
+            (
+                add_to_whitelist(&program_id, &wallet, other, 2),
+                GameInstruction::AddToWhitelist { wallet: other, allocation: 2 },
+            ),
+            (
+                remove_from_whitelist(&program_id, &wallet, other),
+                GameInstruction::RemoveFromWhitelist { wallet: other },
+            ),
+            (
+                set_whitelist_enabled(&program_id, &wallet, true),
+                GameInstruction::SetWhitelistEnabled { enabled: true },
+            ),
+            (
+                debug_dump(&program_id, &[other], Some(wallet), None, Some(mint)),
+                GameInstruction::DebugDump {
+                    authority: Some(wallet),
+                    wallet: None,
+                    mint: Some(mint),
+                },
+            ),
+            (verify_creator(&program_id, &wallet, &mint, None), GameInstruction::VerifyCreator),
+            (
+                verify_collection(&program_id, &mint, &other, &wallet, None),
+                GameInstruction::VerifyCollection,
+            ),
+            (
+                set_mint_window(&program_id, &wallet, 1, 2),
+                GameInstruction::SetMintWindow { start: 1, end: 2 },
+            ),
+            (
+                set_max_per_wallet(&program_id, &wallet, 4),
+                GameInstruction::SetMaxPerWallet { max_per_wallet: 4 },
+            ),
+            (
+                update_config(&program_id, &wallet, UpdateConfigArgs::default()),
+                GameInstruction::UpdateConfig(UpdateConfigArgs::default()),
+            ),
+            (
+                add_config_lines(&program_id, &wallet, vec![(String::from("a"), String::new())]),
+                GameInstruction::AddConfigLines { lines: vec![(String::from("a"), String::new())] },
+            ),
+            (
+                update_merkle_root(&program_id, &wallet, [2; 32]),
+                GameInstruction::UpdateMerkleRoot { new_root: [2; 32] },
+            ),
+            (freeze(&program_id, &wallet, &mint, &other), GameInstruction::Freeze),
+            (thaw(&program_id, &wallet, &mint, &other), GameInstruction::Thaw),
+            (
+                print_edition(&program_id, &wallet, &other, &mint, None, 3),
+                GameInstruction::PrintEdition { edition_number: 3 },
+            ),
+            (
+                initialize_collection(&program_id, &wallet, &mint, collection_args.clone()),
+                GameInstruction::InitializeCollection(collection_args),
+            ),
+            (transfer_nft(&program_id, &wallet, &mint, &other), GameInstruction::TransferNft),
+            (
+                set_primary_sale_happened(&program_id, &wallet, &mint, None),
+                GameInstruction::SetPrimarySaleHappened,
+            ),
+            (seal_collection(&program_id, &wallet, None), GameInstruction::SealCollection),
+            (
+                delegate(&program_id, &wallet, &mint, other),
+                GameInstruction::Delegate { delegate: other },
+            ),
+            (revoke_delegate(&program_id, &wallet, &mint), GameInstruction::RevokeDelegate),
+            (
+                close_token_account(&program_id, &wallet, &other, &wallet),
+                GameInstruction::CloseTokenAccount,
+            ),
+            (get_version(&program_id, &[other]), GameInstruction::GetVersion),
+            (
+                create_session(&program_id, &wallet, other, 5, 6),
+                GameInstruction::CreateSession { session_key: other, expiry: 5, max_mints: 6 },
+            ),
+            (
+                revoke_session(&program_id, &wallet, other),
+                GameInstruction::RevokeSession { session_key: other },
+            ),
+        ];
+        for (instruction, expected) in cases {
+            assert_eq!(instruction.program_id, program_id);
+            assert_eq!(decoded(&instruction), expected);
+        }
+
+        // the airdrop leaves expected_mint default, each recipient brings its own
+        args.expected_mint = Pubkey::default();
+        let recipients = [(other, mint)];
+        let instruction = airdrop(&program_id, &wallet, &recipients, args.clone(), None, false);
+        let expected = GameInstruction::Airdrop { recipients_count: 1, args };
+        assert_eq!(decoded(&instruction), expected);
+    }
+
+    #[test]
+    fn mint_nft_writes_the_mint_into_the_args() {
+        let (payer, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let args = test_mint_args(Pubkey::default());
+        let optional = OptionalMintAccounts::default();
+        let instruction = mint_nft(&crate::id(), &payer, &mint, &payer, &payer, args, &optional);
+        assert!(matches!(
+            decoded(&instruction),
+            GameInstruction::Mint(args) if args.expected_mint == mint
+        ));
+    }
+
+    #[test]
+    fn mint_nft_runs_through_the_processor() {
+        setup();
+        let config = test_config(Pubkey::new_unique());
+        let payer = Pubkey::new_unique();
+        let fixtures = mint_accounts(&config, payer);
+        let mint = fixtures[mint_slot::MINT].key;
+        let instruction = mint_nft(
+            &crate::id(),
+            &payer,
+            &mint,
+            &config.authority,
+            &config.treasury,
+            test_mint_args(Pubkey::default()),
+            &OptionalMintAccounts::default(),
+        );
+        assert_eq!(instruction.accounts.len(), fixtures.len());
+        let (result, accounts) = run(&instruction, &fixtures);
+        result.unwrap();
+        assert_eq!(load_config(&accounts[mint_slot::CONFIG]).minted, 1);
+        assert_eq!(created_metadata().len(), 1);
+    }
+
+    #[test]
+    fn session_mint_runs_through_the_processor() {
+        setup();
+        let config = test_config(Pubkey::new_unique());
+        let (player, session_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut fixtures = mint_accounts(&config, session_key);
+        set_mint_recipient(&mut fixtures, player);
+        let session = Session {
+            player,
+            session_key,
+            expiry: 1_000_060,
+            remaining_mints: 2,
+            bump: 0,
+            version: STATE_VERSION,
+        };
+        fixtures.push(TestAccount::state(
+            pda(&[SESSION_SEED, player.as_ref(), session_key.as_ref()]),
+            crate::id(),
+            &session,
+            Session::LEN,
+        ));
+        let mut args = test_mint_args(Pubkey::default());
+        args.recipient = Some(player);
+        let optional = OptionalMintAccounts {
+            session: true,
+            ..OptionalMintAccounts::default()
+        };
+        let mint = fixtures[mint_slot::MINT].key;
+        let instruction = mint_nft(
+            &crate::id(),
+            &session_key,
+            &mint,
+            &config.authority,
+            &config.treasury,
+            args,
+            &optional,
+        );
+        let (result, accounts) = run(&instruction, &fixtures);
+        result.unwrap();
+        assert_eq!(accounts[mint_slot::ATA].key, ata(&player, &mint));
+        let session: Session =
+            crate::utils::try_from_slice_unchecked(&accounts[mint_slot::SESSION].data).unwrap();
+        assert_eq!(session.remaining_mints, 1);
+    }
+
+    #[test]
+    fn set_price_runs_through_the_processor() {
+        setup();
+        let config = test_config(Pubkey::new_unique());
+        let fixtures = [TestAccount::wallet(config.authority), config_account(&config)];
+        let instruction = set_price(&crate::id(), &config.authority, 42);
+        let (result, accounts) = run(&instruction, &fixtures);
+        result.unwrap();
+        assert_eq!(load_config(&accounts[1]).price_lamports, 42);
+    }
 }
@@ -1,8 +1,12 @@
+#[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
 pub mod error;
+pub mod hashing;
 pub mod instruction;
 pub mod processor;
 pub mod state;
+#[cfg(test)]
+mod test_utils;
 pub mod utils;
 // Export current sdk types for downstream users building with a different sdk version
 pub use solana_program;
@@ -1,21 +1,212 @@
 use borsh::BorshDeserialize;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
+pub use airdrop::*;
+pub use batch_mint::*;
+pub use burn::*;
+pub use close_accounts::*;
+pub use collection::*;
+pub use config::*;
+pub use config_lines::*;
+pub use debug_dump::*;
+pub use delegate::*;
+pub use freeze::*;
 pub use mint::*;
+pub use print_edition::*;
+pub use session::*;
+pub use set_collection::*;
+pub use transfer::*;
+pub use update_metadata::*;
+pub use version::*;
+pub use verify_collection::*;
+pub use verify_creator::*;
+pub use whitelist::*;
 
-use crate::instruction::*;
+use crate::{error::AppError, instruction::GameInstruction};
 
+pub mod airdrop;
+pub mod batch_mint;
+pub mod burn;
+pub mod close_accounts;
+pub mod collection;
+pub mod config;
+pub mod config_lines;
+pub mod debug_dump;
+pub mod delegate;
+pub mod freeze;
 pub mod mint;
+pub mod print_edition;
+pub mod session;
+pub mod set_collection;
+pub mod transfer;
+pub mod update_metadata;
+pub mod version;
+pub mod verify_collection;
+pub mod verify_creator;
+pub mod whitelist;
+
+/// Larger payloads are rejected before borsh allocates anything for them.
+pub const MAX_INSTRUCTION_DATA_LEN: usize = 1024;
 
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     input: &[u8],
 ) -> ProgramResult {
-    let instruction = GameInstruction::try_from_slice(input)?;
+    if input.len() > MAX_INSTRUCTION_DATA_LEN {
+        return Err(AppError::InstructionDataTooLarge.into());
+    }
+    let instruction = GameInstruction::try_from_slice(input)
+        .map_err(|_| AppError::InvalidInstructionData)?;
     match instruction {
         GameInstruction::Mint(args) => {
-            process_mint(program_id, accounts,args.title,args.uri)
+            process_mint(program_id, accounts, args)
+        }
+        GameInstruction::Burn => {
+            process_burn(program_id, accounts)
+        }
+        GameInstruction::UpdateMetadata(args) => {
+            process_update_metadata(program_id, accounts, args)
+        }
+        GameInstruction::BatchMint(items) => {
+            process_batch_mint(program_id, accounts, items)
+        }
+        GameInstruction::BatchCloseAccounts => {
+            process_batch_close_accounts(program_id, accounts)
+        }
+        GameInstruction::InitializeConfig(args) => {
+            process_initialize_config(program_id, accounts, args)
+        }
+        GameInstruction::Transfer { amount } => {
+            process_transfer(program_id, accounts, amount)
+        }
+        GameInstruction::SetPrice { price_lamports } => {
+            process_set_price(program_id, accounts, price_lamports)
+        }
+        GameInstruction::AddDelegate { delegate } => {
+            process_add_delegate(program_id, accounts, delegate)
+        }
+        GameInstruction::RemoveDelegate { delegate } => {
+            process_remove_delegate(program_id, accounts, delegate)
+        }
+        GameInstruction::SetTokenPrice { payment_mint, price_tokens } => {
+            process_set_token_price(program_id, accounts, payment_mint, price_tokens)
+        }
+        GameInstruction::SetCollection => {
+            process_set_collection(program_id, accounts)
+        }
+        GameInstruction::SetUriTemplate { uri_template } => {
+            process_set_uri_template(program_id, accounts, uri_template)
+        }
+        GameInstruction::AddToWhitelist { wallet, allocation } => {
+            process_add_to_whitelist(program_id, accounts, wallet, allocation)
+        }
+        GameInstruction::RemoveFromWhitelist { wallet } => {
+            process_remove_from_whitelist(program_id, accounts, wallet)
+        }
+        GameInstruction::SetWhitelistEnabled { enabled } => {
+            process_set_whitelist_enabled(program_id, accounts, enabled)
+        }
+        GameInstruction::DebugDump { authority, wallet, mint } => {
+            process_debug_dump(program_id, accounts, authority, wallet, mint)
+        }
+        GameInstruction::VerifyCreator => {
+            process_verify_creator(program_id, accounts)
+        }
+        GameInstruction::VerifyCollection => {
+            process_verify_collection(program_id, accounts)
+        }
+        GameInstruction::SetMintWindow { start, end } => {
+            process_set_mint_window(program_id, accounts, start, end)
+        }
+        GameInstruction::SetMaxPerWallet { max_per_wallet } => {
+            process_set_max_per_wallet(program_id, accounts, max_per_wallet)
+        }
+        GameInstruction::UpdateConfig(args) => {
+            process_update_config(program_id, accounts, args)
+        }
+        GameInstruction::AddConfigLines { lines } => {
+            process_add_config_lines(program_id, accounts, lines)
         }
+        GameInstruction::MintWithProof(args, proof) => {
+            process_mint_with_proof(program_id, accounts, args, proof)
+        }
+        GameInstruction::UpdateMerkleRoot { new_root } => {
+            process_update_merkle_root(program_id, accounts, new_root)
+        }
+        GameInstruction::Freeze => {
+            process_freeze(program_id, accounts)
+        }
+        GameInstruction::Thaw => {
+            process_thaw(program_id, accounts)
+        }
+        GameInstruction::PrintEdition { edition_number } => {
+            process_print_edition(program_id, accounts, edition_number)
+        }
+        GameInstruction::InitializeCollection(args) => {
+            process_initialize_collection(program_id, accounts, args)
+        }
+        GameInstruction::TransferUpdateAuthority { new_authority } => {
+            process_transfer_update_authority(program_id, accounts, new_authority)
+        }
+        GameInstruction::TransferNft => {
+            process_transfer_nft(program_id, accounts)
+        }
+        GameInstruction::SetPrimarySaleHappened => {
+            process_set_primary_sale(program_id, accounts)
+        }
+        GameInstruction::SealCollection => {
+            process_seal_collection(program_id, accounts)
+        }
+        GameInstruction::Delegate { delegate } => {
+            process_delegate(program_id, accounts, delegate)
+        }
+        GameInstruction::RevokeDelegate => {
+            process_revoke_delegate(program_id, accounts)
+        }
+        GameInstruction::Airdrop { recipients_count, args } => {
+            process_airdrop(program_id, accounts, recipients_count, args)
+        }
+        GameInstruction::UpdateUri { new_uri } => {
+            process_update_uri(program_id, accounts, new_uri)
+        }
+        GameInstruction::CloseTokenAccount => {
+            process_close_account(program_id, accounts)
+        }
+        GameInstruction::GetVersion => {
+            process_get_version(program_id, accounts)
+        }
+        GameInstruction::CreateSession { session_key, expiry, max_mints } => {
+            process_create_session(program_id, accounts, session_key, expiry, max_mints)
+        }
+        GameInstruction::RevokeSession { session_key } => {
+            process_revoke_session(program_id, accounts, session_key)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn instruction_data_over_the_limit_is_rejected() {
+        setup();
+        let input = vec![0; MAX_INSTRUCTION_DATA_LEN + 1];
+        assert_eq!(
+            process_instruction(&crate::id(), &[], &input),
+            Err(AppError::InstructionDataTooLarge.into())
+        );
+    }
+
+    #[test]
+    fn instruction_data_at_the_limit_is_decoded() {
+        setup();
+        let input = vec![0xff; MAX_INSTRUCTION_DATA_LEN];
+        assert_eq!(
+            process_instruction(&crate::id(), &[], &input),
+            Err(AppError::InvalidInstructionData.into())
+        );
     }
 }
@@ -0,0 +1,314 @@
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    instruction::MintNftArgs,
+    processor::{
+        batch_mint::MAX_BATCH_SIZE,
+        collection::CollectionAccounts,
+        config::assert_config_authority,
+        mint::*,
+    },
+    utils::*,
+};
+
+/// Accounts per recipient: wallet, mint, ata, metadata, edition, record.
+const RECIPIENT_ACCOUNTS_LEN: usize = 6;
+
+/// Mints one NFT from `args` to each recipient wallet. Only the config
+/// authority may airdrop; it pays the rent but no mint price, and the
+/// whitelist and wallet limits don't apply. Otherwise every NFT goes through
+/// the same `mint_one` as a Mint, so supply, seal, collection, config lines
+/// and traits are handled alike.
+pub fn process_airdrop(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    recipients_count: u8,
+    args: MintNftArgs,
+) -> ProgramResult {
+    let count = recipients_count as usize;
+    if count == 0 {
+        msg!("Airdrop needs at least one recipient");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if count > MAX_BATCH_SIZE {
+        return Err(AppError::BatchTooLarge.into());
+    }
+    // each recipient has its own wallet and mint, and there is no single
+    // mint to return, so args naming one would be silently ignored
+    if args.recipient.is_some() || args.expected_mint != Pubkey::default() || args.return_mint {
+        msg!("Airdrop args can't name a recipient, an expected mint or return the mint");
+        return Err(AppError::InconsistentMintArgs.into());
+    }
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let signer_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let ass_token_program_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let collection = CollectionAccounts::next_optional(program_id, account_info_iter)?;
+    let draw = DrawAccounts {
+        lines_info: optional_account(program_id, account_info_iter)?,
+        slot_hashes_info: optional_account(program_id, account_info_iter)?,
+    };
+
+    assert_config_authority(program_id, config_info, signer_info)?;
+    let shared = MintAccounts {
+        authority_info,
+        signer_info,
+        token_program_info,
+        ass_token_program_info,
+        rent_info,
+        system_info,
+        metadata_program_info,
+    };
+    assert_mint_programs(&shared)?;
+    let authority_bump = assert_mint_authority(program_id, authority_info)?;
+    let recipient_accounts = account_info_iter.as_slice();
+    if recipient_accounts.len() != count * RECIPIENT_ACCOUNTS_LEN {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    for group in recipient_accounts.chunks(RECIPIENT_ACCOUNTS_LEN) {
+        let nft = NftAccounts {
+            recipient_info: &group[0],
+            mint_info: &group[1],
+            ata_info: &group[2],
+            metadata_info: &group[3],
+            edition_info: &group[4],
+            record_info: &group[5],
+        };
+        assert_signer(nft.mint_info)?;
+        msg!("Airdrop to {}", nft.recipient_info.key);
+        mint_one(
+            program_id,
+            &shared,
+            &nft,
+            config_info,
+            &draw,
+            collection.as_ref(),
+            args.clone(),
+            MintKind::Airdrop,
+            authority_bump,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::{
+            CollectionConfig, ConfigLine, ConfigLinesHeader, MintRecord, CONFIG_LINES_SEED,
+            MINT_AUTHORITY_SEED, RECORD_SEED, STATE_VERSION,
+        },
+        test_utils::*,
+    };
+    use borsh::BorshSerialize;
+    use mpl_token_metadata::pda::{find_master_edition_account, find_metadata_account};
+    use solana_program::system_program;
+
+    const LINES: usize = 11;
+    const SLOT_HASHES: usize = 12;
+
+    /// Airdrop accounts of `config` for `recipients` fresh wallets, signed by
+    /// the config authority.
+    fn airdrop_accounts(config: &CollectionConfig, recipients: usize) -> Vec<TestAccount> {
+        let authority = pda(&[MINT_AUTHORITY_SEED, crate::id().as_ref()]);
+        let mut accounts = vec![
+            TestAccount::new(authority, crate::id(), vec![]),
+            TestAccount::wallet(config.authority).signer(),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(spl_associated_token_account::id()),
+            TestAccount::rent(),
+            TestAccount::program(system_program::id()),
+            TestAccount::program(mpl_token_metadata::id()),
+            config_account(config),
+        ];
+        accounts.extend((0..5).map(|_| absent()));
+        for _ in 0..recipients {
+            let (wallet, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+            accounts.extend([
+                TestAccount::wallet(wallet),
+                TestAccount::mint(mint, &spl_token::id(), &authority, 0).signer(),
+                TestAccount::token_account(
+                    ata(&wallet, &mint),
+                    &spl_token::id(),
+                    &mint,
+                    &wallet,
+                    1,
+                ),
+                TestAccount::empty(find_metadata_account(&mint).0),
+                TestAccount::empty(find_master_edition_account(&mint).0),
+                TestAccount::new(
+                    pda(&[RECORD_SEED, mint.as_ref()]),
+                    crate::id(),
+                    vec![0; MintRecord::LEN],
+                ),
+            ]);
+        }
+        accounts
+    }
+
+    /// ConfigLines account of the config at `config` holding `uris`.
+    fn lines_account(config: &Pubkey, uris: &[&str]) -> TestAccount {
+        let (key, bump) =
+            Pubkey::find_program_address(&[CONFIG_LINES_SEED, config.as_ref()], &crate::id());
+        let total = uris.len() as u32;
+        let header = ConfigLinesHeader {
+            total,
+            remaining: total,
+            bump,
+            version: STATE_VERSION,
+        };
+        let mut data = vec![0; ConfigLine::offset(total)];
+        header.serialize(&mut &mut data[..ConfigLinesHeader::LEN]).unwrap();
+        for (index, uri) in uris.iter().enumerate() {
+            let offset = ConfigLine::offset(index as u32) + ConfigLine::URI_OFFSET;
+            data[offset..offset + uri.len()].copy_from_slice(uri.as_bytes());
+        }
+        TestAccount::new(key, crate::id(), data)
+    }
+
+    #[test]
+    fn airdrop_mints_to_each_recipient_for_free() {
+        setup();
+        let mut config = test_config(Pubkey::new_unique());
+        config.price_lamports = 1_000;
+        config.max_per_wallet = 1;
+        config.whitelist_enabled = true;
+        let mut accounts = airdrop_accounts(&config, 2);
+        process_airdrop(
+            &crate::id(),
+            &infos(&mut accounts),
+            2,
+            test_mint_args(Pubkey::default()),
+        )
+        .unwrap();
+
+        assert_eq!(load_config(&accounts[7]).minted, 2);
+        let pays_treasury = |call: &Invocation| {
+            call.instruction.accounts.iter().any(|meta| meta.pubkey == config.treasury)
+        };
+        assert!(!invoked().iter().any(pays_treasury));
+        assert_eq!(created_metadata().len(), 2);
+        let first_record = load_record(&accounts[13 + 5]);
+        assert_eq!(first_record.config, accounts[7].key);
+    }
+
+    #[test]
+    fn airdrop_draws_config_lines_of_a_randomized_config() {
+        setup();
+        let mut config = test_config(Pubkey::new_unique());
+        config.randomized = true;
+        let mut accounts = airdrop_accounts(&config, 2);
+        let uris = ["https://example.com/a.json", "https://example.com/b.json"];
+        accounts[LINES] = lines_account(&accounts[7].key, &uris);
+        accounts[SLOT_HASHES] = TestAccount::slot_hashes([7; 32]);
+        process_airdrop(
+            &crate::id(),
+            &infos(&mut accounts),
+            2,
+            test_mint_args(Pubkey::default()),
+        )
+        .unwrap();
+
+        let mut drawn: Vec<_> = created_metadata().into_iter().map(|data| data.uri).collect();
+        drawn.sort();
+        assert_eq!(drawn, uris);
+
+        setup();
+        let mut accounts = airdrop_accounts(&config, 1);
+        assert_eq!(
+            process_airdrop(
+                &crate::id(),
+                &infos(&mut accounts),
+                1,
+                test_mint_args(Pubkey::default()),
+            ),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn airdrop_draws_traits_per_recipient() {
+        setup();
+        let config = test_config(Pubkey::new_unique());
+        let mut accounts = airdrop_accounts(&config, 2);
+        accounts[SLOT_HASHES] = TestAccount::slot_hashes([7; 32]);
+        let mut args = test_mint_args(Pubkey::default());
+        args.trait_options = vec![4];
+        args.uri = String::from("https://example.com/{traits}.json");
+        process_airdrop(&crate::id(), &infos(&mut accounts), 2, args).unwrap();
+
+        let created = created_metadata();
+        assert_eq!(created.len(), 2);
+        assert!(created.iter().all(|data| !data.uri.contains("{traits}")));
+        // several recipients have no single draw to report
+        assert_eq!(return_data(), None);
+    }
+
+    #[test]
+    fn airdrop_rejects_args_naming_a_single_mint() {
+        let config = test_config(Pubkey::new_unique());
+        let rejected: [fn(&mut MintNftArgs); 3] = [
+            |args| args.recipient = Some(Pubkey::new_unique()),
+            |args| args.expected_mint = Pubkey::new_unique(),
+            |args| args.return_mint = true,
+        ];
+        for reject in rejected {
+            setup();
+            let mut accounts = airdrop_accounts(&config, 1);
+            let mut args = test_mint_args(Pubkey::default());
+            reject(&mut args);
+            assert_eq!(
+                process_airdrop(&crate::id(), &infos(&mut accounts), 1, args),
+                Err(AppError::InconsistentMintArgs.into())
+            );
+            assert!(invoked().is_empty());
+        }
+    }
+
+    #[test]
+    fn airdrop_to_nobody_is_invalid() {
+        setup();
+        let config = test_config(Pubkey::new_unique());
+        let mut accounts = airdrop_accounts(&config, 0);
+        assert_eq!(
+            process_airdrop(
+                &crate::id(),
+                &infos(&mut accounts),
+                0,
+                test_mint_args(Pubkey::default()),
+            ),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn airdrop_is_for_the_config_authority_only() {
+        setup();
+        let config = test_config(Pubkey::new_unique());
+        let mut accounts = airdrop_accounts(&config, 1);
+        accounts[1] = TestAccount::wallet(Pubkey::new_unique()).signer();
+        assert_eq!(
+            process_airdrop(
+                &crate::id(),
+                &infos(&mut accounts),
+                1,
+                test_mint_args(Pubkey::default()),
+            ),
+            Err(AppError::InvalidAuthority.into())
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    instruction::MintNftArgs,
+    processor::{
+        collection::{assert_config_collection, verify_collection_item, CollectionAccounts},
+        config_lines::draw_config_line,
+        mint::*,
+        whitelist::consume_whitelist,
+    },
+    utils::*,
+};
+
+pub const MAX_BATCH_SIZE: usize = 5;
+
+/// Accounts created per NFT: mint, ata, metadata, edition, record.
+const NFT_ACCOUNTS_LEN: usize = 5;
+
+pub fn process_batch_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    items: Vec<MintNftArgs>,
+) -> ProgramResult {
+    if items.len() > MAX_BATCH_SIZE {
+        return Err(AppError::BatchTooLarge.into());
+    }
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let signer_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let ass_token_program_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let treasury_info = next_account_info(account_info_iter)?;
+    let payment = PaymentAccounts::next_optional(program_id, account_info_iter)?;
+    let whitelist_info = optional_account(program_id, account_info_iter)?;
+    let counter_info = optional_account(program_id, account_info_iter)?;
+    let lines_info = optional_account(program_id, account_info_iter)?;
+    let slot_hashes_info = optional_account(program_id, account_info_iter)?;
+    let collection = CollectionAccounts::next_optional(program_id, account_info_iter)?;
+    let fee_recipient_info = optional_account(program_id, account_info_iter)?;
+
+    assert_signer(signer_info)?;
+    let shared = MintAccounts {
+        authority_info,
+        signer_info,
+        token_program_info,
+        ass_token_program_info,
+        rent_info,
+        system_info,
+        metadata_program_info,
+    };
+
+    assert_mint_programs(&shared)?;
+    let authority_bump = assert_mint_authority(program_id, authority_info)?;
+    let nft_accounts = account_info_iter.as_slice();
+    if nft_accounts.len() != items.len() * NFT_ACCOUNTS_LEN {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let count = items.len() as u64;
+    let config = reserve_supply(program_id, config_info, count)?;
+    assert_can_mint(&config, signer_info)?;
+
+    // validate every item before the first CPI so a bad item never leaves
+    // earlier NFTs half created
+    let mut batch = Vec::with_capacity(items.len());
+    for (group, args) in nft_accounts.chunks(NFT_ACCOUNTS_LEN).zip(items) {
+        let nft = NftAccounts {
+            mint_info: &group[0],
+            ata_info: &group[1],
+            metadata_info: &group[2],
+            edition_info: &group[3],
+            record_info: &group[4],
+            recipient_info: signer_info,
+        };
+        let record_bump = assert_record_derivation(program_id, &nft)?;
+        assert_ata_derivation(&nft, token_program_info)?;
+        assert_metadata_derivation(&nft)?;
+        assert_expected_mint(nft.mint_info, &args.expected_mint)?;
+        if args.recipient.is_some_and(|recipient| recipient != *signer_info.key) {
+            msg!("BatchMint only mints to the signer");
+            return Err(AppError::InconsistentMintArgs.into());
+        }
+        if args.strict && args.return_mint {
+            msg!("Strict: return_mint is not supported by BatchMint");
+            return Err(AppError::InconsistentMintArgs.into());
+        }
+        let params = validate_mint_args(signer_info.key, args, &config.default_creators)?;
+        batch.push((nft, params, record_bump));
+    }
+    consume_whitelist(program_id, &config, config_info, signer_info, whitelist_info, count)?;
+    count_wallet_mints(
+        program_id,
+        &config,
+        config_info,
+        &shared,
+        signer_info.key,
+        counter_info,
+        count,
+    )?;
+    let first_index = config.minted - count;
+    for (offset, (nft, params, _)) in batch.iter_mut().enumerate() {
+        apply_uri_template(&config, params, first_index + offset as u64)?;
+        assert_config_collection(&config, collection.as_ref(), params)?;
+        draw_config_line(
+            program_id,
+            &config,
+            config_info,
+            lines_info,
+            slot_hashes_info,
+            params,
+            nft.mint_info.key.as_ref(),
+        )?;
+        draw_traits(slot_hashes_info, params, nft.mint_info.key.as_ref())?;
+    }
+    charge_mint_price(&config, &shared, treasury_info, fee_recipient_info, payment, count)?;
+
+    for (index, (nft, params, record_bump)) in batch.into_iter().enumerate() {
+        msg!("Batch Mint {}", index);
+        let metadata_info = nft.metadata_info;
+        mint_nft(program_id, &shared, &nft, config_info.key, params, authority_bump, record_bump)?;
+        if let (Some(_), Some(collection)) = (config.collection_mint, &collection) {
+            verify_collection_item(program_id, &shared, metadata_info, collection, authority_bump)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use borsh::BorshDeserialize;
+    use mpl_token_metadata::instruction::MetadataInstruction;
+
+    /// BatchMint accounts and args of `count` plain NFTs by a fresh wallet
+    /// under a free config, with every optional slot absent.
+    fn batch_accounts(count: usize) -> (Vec<TestAccount>, Vec<MintNftArgs>) {
+        let mut mint = mint_accounts(&test_config(Pubkey::new_unique()), Pubkey::new_unique());
+        // the shared accounts keep their Mint order, minus the NFT slots
+        let mut accounts: Vec<_> = [mint_slot::AUTHORITY, mint_slot::SIGNER]
+            .into_iter()
+            .chain(mint_slot::TOKEN_PROGRAM..mint_slot::METADATA)
+            .chain([mint_slot::CONFIG, mint_slot::TREASURY])
+            .map(|slot| mint[slot].clone())
+            .collect();
+        accounts.extend((0..11).map(|_| absent()));
+        let mut items = vec![];
+        for _ in 0..count {
+            let key = Pubkey::new_unique();
+            set_mint(&mut mint, key);
+            let group = [
+                mint_slot::MINT,
+                mint_slot::ATA,
+                mint_slot::METADATA,
+                mint_slot::EDITION,
+                mint_slot::RECORD,
+            ];
+            accounts.extend(group.iter().map(|&slot| mint[slot].clone()));
+            items.push(test_mint_args(key));
+        }
+        (accounts, items)
+    }
+
+    #[test]
+    fn batch_mint_creates_metadata_for_every_item() {
+        setup();
+        let (mut accounts, items) = batch_accounts(3);
+        let mints: Vec<_> = items.iter().map(|args| args.expected_mint).collect();
+        process_batch_mint(&crate::id(), &infos(&mut accounts), items).unwrap();
+
+        assert_eq!(created_metadata().len(), 3);
+        let created_for: Vec<_> = invoked_program(&mpl_token_metadata::id())
+            .iter()
+            .filter(|call| matches!(
+                MetadataInstruction::try_from_slice(&call.instruction.data),
+                Ok(MetadataInstruction::CreateMetadataAccountV3(_))
+            ))
+            .map(|call| call.instruction.accounts[1].pubkey)
+            .collect();
+        assert_eq!(created_for, mints);
+        assert_eq!(load_config(&accounts[7]).minted, 3);
+    }
+
+    #[test]
+    fn batch_mint_needs_one_account_group_per_item() {
+        setup();
+        let (mut accounts, mut items) = batch_accounts(3);
+        items.push(test_mint_args(Pubkey::new_unique()));
+        assert_eq!(
+            process_batch_mint(&crate::id(), &infos(&mut accounts), items),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn batch_mint_aborts_on_a_bad_item() {
+        setup();
+        let (mut accounts, mut items) = batch_accounts(3);
+        items[2].uri = String::new();
+        assert_eq!(
+            process_batch_mint(&crate::id(), &infos(&mut accounts), items),
+            Err(AppError::InvalidUri.into())
+        );
+        assert!(invoked().is_empty());
+    }
+}
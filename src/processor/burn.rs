@@ -0,0 +1,178 @@
+use mpl_token_metadata::{
+    instruction::burn_nft,
+    state::{Metadata, PREFIX},
+};
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{error::AppError, utils::*};
+
+pub fn process_burn(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let ata_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let edition_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let collection_metadata_info = optional_account(program_id, account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    assert_metadata_program(metadata_program_info)?;
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    assert_burn_collection(metadata_info, collection_metadata_info)?;
+    let token_program = TokenProgram::from_account_info(token_program_info)?;
+    let ata = assert_token_account_owner(&token_program, ata_info, owner_info.key)?;
+    if ata.mint != *mint_info.key || ata.amount == 0 {
+        return Err(AppError::NotTokenOwner.into());
+    }
+    if ata.amount != 1 {
+        return Err(AppError::InvalidTokenAmount.into());
+    }
+
+    let mut account_infos = vec![
+        metadata_info.clone(),
+        owner_info.clone(),
+        mint_info.clone(),
+        ata_info.clone(),
+        edition_info.clone(),
+        token_program_info.clone(),
+        metadata_program_info.clone(),
+    ];
+    account_infos.extend(collection_metadata_info.cloned());
+
+    // burn_nft burns the token, closes the token account and closes the
+    // metadata and master edition, returning all rent to the owner
+    msg!("Burn NFT");
+    invoke(
+        &burn_nft(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *owner_info.key,
+            *mint_info.key,
+            *ata_info.key,
+            *edition_info.key,
+            *token_program_info.key,
+            collection_metadata_info.map(|info| *info.key),
+        ),
+        &account_infos,
+    )?;
+    Ok(())
+}
+
+/// The metadata program only burns a verified collection item together with
+/// the collection metadata, whose size it decrements. When given, that
+/// account must be the metadata PDA of the NFT's collection.
+fn assert_burn_collection(
+    metadata_info: &AccountInfo,
+    collection_metadata_info: Option<&AccountInfo>,
+) -> ProgramResult {
+    let metadata = Metadata::from_account_info(metadata_info)?;
+    let collection = match metadata.collection {
+        Some(collection) => collection,
+        None => return Ok(()),
+    };
+    let collection_metadata_info = match collection_metadata_info {
+        Some(collection_metadata_info) => collection_metadata_info,
+        None if collection.verified => {
+            msg!("Collection metadata of {} missing", collection.key);
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        None => return Ok(()),
+    };
+    let metadata_program_id = mpl_token_metadata::id();
+    assert_derivation(
+        &metadata_program_id,
+        collection_metadata_info,
+        &[
+            PREFIX.as_bytes(),
+            metadata_program_id.as_ref(),
+            collection.key.as_ref(),
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use mpl_token_metadata::{pda::find_master_edition_account, state::Collection};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Accounts of a Burn of an NFT verified into `collection_mint`, with the
+    /// collection metadata slot holding `collection_metadata`.
+    fn burn_accounts(collection_mint: Pubkey, collection_metadata: TestAccount) -> Vec<TestAccount> {
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut metadata = test_metadata(mint, Pubkey::new_unique());
+        metadata.collection = Some(Collection {
+            key: collection_mint,
+            verified: true,
+        });
+        vec![
+            TestAccount::wallet(owner).signer(),
+            TestAccount::mint(mint, &spl_token::id(), &Pubkey::new_unique(), 0),
+            TestAccount::token_account(Pubkey::new_unique(), &spl_token::id(), &mint, &owner, 1),
+            metadata_account(&metadata),
+            TestAccount::new(
+                find_master_edition_account(&mint).0,
+                mpl_token_metadata::id(),
+                vec![],
+            ),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(mpl_token_metadata::id()),
+            collection_metadata,
+        ]
+    }
+
+    fn collection_metadata_account(collection_mint: Pubkey) -> TestAccount {
+        metadata_account(&test_metadata(collection_mint, Pubkey::new_unique()))
+    }
+
+    #[test]
+    fn burn_forwards_the_collection_metadata() {
+        setup();
+        let collection_mint = Pubkey::new_unique();
+        let mut accounts = burn_accounts(collection_mint, collection_metadata_account(collection_mint));
+        process_burn(&crate::id(), &infos(&mut accounts)).unwrap();
+
+        let calls = invoked_program(&mpl_token_metadata::id());
+        assert_eq!(calls.len(), 1);
+        let burn_accounts = &calls[0].instruction.accounts;
+        assert_eq!(burn_accounts.len(), 7);
+        assert_eq!(burn_accounts[6].pubkey, accounts[7].key);
+    }
+
+    #[test]
+    fn burn_of_a_verified_item_needs_the_collection_metadata() {
+        setup();
+        let mut accounts = burn_accounts(Pubkey::new_unique(), absent());
+        assert_eq!(
+            process_burn(&crate::id(), &infos(&mut accounts)),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn burn_rejects_the_metadata_of_another_collection() {
+        setup();
+        let mut accounts =
+            burn_accounts(Pubkey::new_unique(), collection_metadata_account(Pubkey::new_unique()));
+        assert_eq!(
+            process_burn(&crate::id(), &infos(&mut accounts)),
+            Err(AppError::InvalidDerivedKey.into())
+        );
+    }
+}
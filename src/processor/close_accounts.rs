@@ -0,0 +1,172 @@
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use spl_token::instruction::close_account;
+use spl_token_2022::instruction as token_2022;
+
+use crate::{error::AppError, utils::*};
+
+pub const MAX_CLOSE_ACCOUNTS: usize = 8;
+
+pub fn process_batch_close_accounts(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    let token_program = TokenProgram::from_account_info(token_program_info)?;
+    let token_accounts = account_info_iter.as_slice();
+    if token_accounts.len() > MAX_CLOSE_ACCOUNTS {
+        return Err(AppError::BatchTooLarge.into());
+    }
+
+    for token_account_info in token_accounts {
+        let token_account =
+            assert_token_account_owner(&token_program, token_account_info, owner_info.key)?;
+        if token_account.amount != 0 {
+            msg!("Skip {}: balance {}", token_account_info.key, token_account.amount);
+            continue;
+        }
+
+        msg!("Close Account {}", token_account_info.key);
+        invoke(
+            &close_token_account(
+                &token_program,
+                token_account_info.key,
+                owner_info.key,
+                owner_info.key,
+            )?,
+            &[
+                token_account_info.clone(),
+                owner_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Closes one empty token account of the signer, sending its rent to
+/// `destination_info`. Allowed after a seal, like BatchCloseAccounts.
+pub fn process_close_account(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    let token_program = TokenProgram::from_account_info(token_program_info)?;
+    let token_account =
+        assert_token_account_owner(&token_program, token_account_info, owner_info.key)?;
+    if token_account.amount != 0 {
+        return Err(AppError::AccountNotEmpty.into());
+    }
+
+    msg!("Close Account {}", token_account_info.key);
+    invoke(
+        &close_token_account(
+            &token_program,
+            token_account_info.key,
+            destination_info.key,
+            owner_info.key,
+        )?,
+        &[
+            token_account_info.clone(),
+            destination_info.clone(),
+            owner_info.clone(),
+            token_program_info.clone(),
+        ],
+    )
+}
+
+/// `close_account` of whichever token program owns the account.
+fn close_token_account(
+    token_program: &TokenProgram,
+    account: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    match token_program {
+        TokenProgram::Legacy(_) => {
+            close_account(token_program.id(), account, destination, owner, &[])
+        }
+        TokenProgram::Token2022(_) => {
+            token_2022::close_account(token_program.id(), account, destination, owner, &[])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn batch_accounts(owner: Pubkey, amounts: &[u64]) -> Vec<TestAccount> {
+        let mut accounts =
+            vec![TestAccount::wallet(owner).signer(), TestAccount::program(spl_token::id())];
+        accounts.extend(amounts.iter().map(|amount| {
+            TestAccount::token_account(
+                Pubkey::new_unique(),
+                &spl_token::id(),
+                &Pubkey::new_unique(),
+                &owner,
+                *amount,
+            )
+        }));
+        accounts
+    }
+
+    #[test]
+    fn batch_close_closes_three_empty_accounts() {
+        setup();
+        let mut accounts = batch_accounts(Pubkey::new_unique(), &[0, 0, 0]);
+        process_batch_close_accounts(&crate::id(), &infos(&mut accounts)).unwrap();
+
+        let closed: Vec<Pubkey> = invoked_program(&spl_token::id())
+            .iter()
+            .map(|call| call.instruction.accounts[0].pubkey)
+            .collect();
+        assert_eq!(closed, [accounts[2].key, accounts[3].key, accounts[4].key]);
+    }
+
+    #[test]
+    fn batch_close_skips_accounts_with_a_balance() {
+        setup();
+        let mut accounts = batch_accounts(Pubkey::new_unique(), &[0, 1, 0]);
+        process_batch_close_accounts(&crate::id(), &infos(&mut accounts)).unwrap();
+        assert_eq!(invoked_program(&spl_token::id()).len(), 2);
+    }
+
+    #[test]
+    fn batch_close_rejects_an_unknown_token_program() {
+        setup();
+        let mut accounts = batch_accounts(Pubkey::new_unique(), &[0]);
+        accounts[1] = TestAccount::program(Pubkey::new_unique());
+        assert_eq!(
+            process_batch_close_accounts(&crate::id(), &infos(&mut accounts)),
+            Err(ProgramError::IncorrectProgramId)
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn batch_close_rejects_an_account_of_another_owner() {
+        setup();
+        let mut accounts = batch_accounts(Pubkey::new_unique(), &[0]);
+        accounts.extend(batch_accounts(Pubkey::new_unique(), &[0]).pop());
+        assert_eq!(
+            process_batch_close_accounts(&crate::id(), &infos(&mut accounts)),
+            Err(AppError::InvalidOwner.into())
+        );
+    }
+}
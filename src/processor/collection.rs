@@ -0,0 +1,239 @@
+use mpl_token_metadata::{
+    instruction::{create_master_edition_v3, create_metadata_accounts_v3, verify_sized_collection_item},
+    state::{Collection, CollectionDetails, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH},
+};
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    instruction::InitializeCollectionArgs,
+    processor::{
+        config::assert_config_authority,
+        mint::{assert_mint_authority, mint_tokens, MintAccounts, MintParams},
+        set_collection::assert_collection_accounts,
+    },
+    state::{CollectionConfig, MINT_AUTHORITY_SEED},
+    utils::*,
+};
+
+/// Collection NFT accounts a mint is verified into, passed as optional slots
+/// so configs without a collection can leave them absent.
+pub struct CollectionAccounts<'a, 'b> {
+    pub mint_info: &'a AccountInfo<'b>,
+    pub metadata_info: &'a AccountInfo<'b>,
+    pub edition_info: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> CollectionAccounts<'a, 'b> {
+    pub fn next_optional<I: Iterator<Item = &'a AccountInfo<'b>>>(
+        program_id: &Pubkey,
+        iter: &mut I,
+    ) -> Result<Option<Self>, ProgramError> {
+        let mint_info = optional_account(program_id, iter)?;
+        let metadata_info = optional_account(program_id, iter)?;
+        let edition_info = optional_account(program_id, iter)?;
+        Ok(match (mint_info, metadata_info, edition_info) {
+            (Some(mint_info), Some(metadata_info), Some(edition_info)) => Some(CollectionAccounts {
+                mint_info,
+                metadata_info,
+                edition_info,
+            }),
+            _ => None,
+        })
+    }
+}
+
+/// Points `params` at the config collection when there is one. The passed
+/// collection must be the configured one, so items can't be attached to a
+/// foreign collection.
+pub fn assert_config_collection<'a, 'b>(
+    config: &CollectionConfig,
+    collection: Option<&CollectionAccounts<'a, 'b>>,
+    params: &mut MintParams,
+) -> ProgramResult {
+    let collection_mint = match config.collection_mint {
+        Some(collection_mint) => collection_mint,
+        None => return Ok(()),
+    };
+    let collection = collection.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if *collection.mint_info.key != collection_mint {
+        return Err(AppError::InvalidCollectionMint.into());
+    }
+    assert_collection_accounts(
+        collection.mint_info,
+        collection.metadata_info,
+        collection.edition_info,
+    )?;
+    params.collection = Some(Collection {
+        key: collection_mint,
+        verified: false,
+    });
+    Ok(())
+}
+
+/// Verifies a freshly created metadata into the config collection, signed by
+/// the mint authority PDA that is the collection's update authority.
+pub fn verify_collection_item<'a, 'b>(
+    program_id: &Pubkey,
+    shared: &MintAccounts<'a, 'b>,
+    metadata_info: &AccountInfo<'b>,
+    collection: &CollectionAccounts<'a, 'b>,
+    authority_bump: u8,
+) -> ProgramResult {
+    let authority_seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, program_id.as_ref(), &[authority_bump]];
+    msg!("Verify Sized Collection Item");
+    invoke_signed(
+        &verify_sized_collection_item(
+            *shared.metadata_program_info.key,
+            *metadata_info.key,
+            *shared.authority_info.key,
+            *shared.signer_info.key,
+            *collection.mint_info.key,
+            *collection.metadata_info.key,
+            *collection.edition_info.key,
+            None,
+        ),
+        &[
+            metadata_info.clone(),
+            shared.authority_info.clone(),
+            shared.signer_info.clone(),
+            collection.mint_info.clone(),
+            collection.metadata_info.clone(),
+            collection.edition_info.clone(),
+            shared.metadata_program_info.clone(),
+        ],
+        &[authority_seeds],
+    )
+}
+
+/// Mints the collection NFT of a config, with the mint authority PDA as its
+/// update authority so mints can be verified into it without the authority
+/// key.
+pub fn process_initialize_collection(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: InitializeCollectionArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let ata_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let edition_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let ass_token_program_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, config_authority_info)?;
+    if config.collection_mint.is_some() {
+        return Err(AppError::AlreadyInitialized.into());
+    }
+    let InitializeCollectionArgs { name, symbol, uri } = args;
+    if uri.is_empty() {
+        return Err(AppError::InvalidUri.into());
+    }
+    if uri.len() > MAX_URI_LENGTH {
+        return Err(AppError::UriTooLong.into());
+    }
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(AppError::NameTooLong.into());
+    }
+    if symbol.len() > MAX_SYMBOL_LENGTH {
+        return Err(AppError::SymbolTooLong.into());
+    }
+    let authority_bump = assert_mint_authority(program_id, authority_info)?;
+    assert_metadata_program(metadata_program_info)?;
+
+    let shared = MintAccounts {
+        authority_info,
+        signer_info: config_authority_info,
+        token_program_info,
+        ass_token_program_info,
+        rent_info,
+        system_info,
+        metadata_program_info,
+    };
+    mint_tokens(
+        program_id,
+        &shared,
+        mint_info,
+        ata_info,
+        config_authority_info,
+        0,
+        1,
+        authority_bump,
+    )?;
+
+    let authority_seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, program_id.as_ref(), &[authority_bump]];
+    msg!("Create Collection Metadata");
+    invoke_signed(
+        &create_metadata_accounts_v3(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *mint_info.key,
+            *authority_info.key,
+            *config_authority_info.key,
+            *authority_info.key,
+            name,
+            symbol,
+            uri,
+            None,
+            0,
+            true,
+            true,
+            None,
+            None,
+            Some(CollectionDetails::V1 { size: 0 }),
+        ),
+        &[
+            metadata_info.clone(),
+            mint_info.clone(),
+            authority_info.clone(),
+            config_authority_info.clone(),
+            metadata_program_info.clone(),
+            system_info.clone(),
+            rent_info.clone(),
+        ],
+        &[authority_seeds],
+    )?;
+
+    msg!("Create Collection Master Edition");
+    invoke_signed(
+        &create_master_edition_v3(
+            *metadata_program_info.key,
+            *edition_info.key,
+            *mint_info.key,
+            *authority_info.key,
+            *authority_info.key,
+            *metadata_info.key,
+            *config_authority_info.key,
+            Some(0),
+        ),
+        &[
+            edition_info.clone(),
+            mint_info.clone(),
+            authority_info.clone(),
+            config_authority_info.clone(),
+            metadata_info.clone(),
+            metadata_program_info.clone(),
+            token_program_info.clone(),
+            system_info.clone(),
+            rent_info.clone(),
+        ],
+        &[authority_seeds],
+    )?;
+
+    config.collection_mint = Some(*mint_info.key);
+    config.save(config_info)
+}
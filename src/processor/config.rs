@@ -0,0 +1,614 @@
+use mpl_token_metadata::{instruction::update_metadata_accounts_v2, state::PREFIX};
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    instruction::{InitializeConfigArgs, UpdateConfigArgs},
+    processor::mint::assert_mint_authority,
+    state::{
+        CollectionConfig, MintRecord, CONFIG_SEED, MINT_AUTHORITY_SEED, RECORD_SEED, STATE_VERSION,
+    },
+    utils::*,
+};
+
+pub fn process_initialize_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: InitializeConfigArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_info = next_account_info(account_info_iter)?;
+
+    assert_signer(authority_info)?;
+    let bump = assert_derivation(
+        program_id,
+        config_info,
+        &[CONFIG_SEED, authority_info.key.as_ref()],
+    )?;
+    if !config_info.data_is_empty() {
+        return Err(AppError::AlreadyInitialized.into());
+    }
+
+    msg!("Create Config");
+    create_or_allocate_account_raw(
+        *program_id,
+        config_info,
+        rent_info,
+        system_info,
+        authority_info,
+        CollectionConfig::LEN,
+        &[CONFIG_SEED, authority_info.key.as_ref(), &[bump]],
+    )?;
+    let config = CollectionConfig {
+        authority: *authority_info.key,
+        max_supply: args.max_supply,
+        minted: 0,
+        price_lamports: args.price_lamports,
+        treasury: args.treasury,
+        bump,
+        version: STATE_VERSION,
+        delegates: vec![],
+        payment_mint: None,
+        price_tokens: 0,
+        uri_template: None,
+        whitelist_enabled: false,
+        mint_start: 0,
+        mint_end: 0,
+        max_per_wallet: 0,
+        is_paused: false,
+        randomized: false,
+        merkle_root: [0; 32],
+        collection_mint: None,
+        sealed: false,
+        protocol_fee_bps: 0,
+        fee_recipient: Pubkey::default(),
+        default_creators: vec![],
+    };
+    config.save(config_info)
+}
+
+/// Loads the config and checks `authority_info` is its signing authority.
+pub fn assert_config_authority(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+    authority_info: &AccountInfo,
+) -> Result<CollectionConfig, ProgramError> {
+    assert_signer(authority_info)?;
+    let config = CollectionConfig::from_account_info(program_id, config_info)?;
+    if config.authority != *authority_info.key {
+        return Err(AppError::InvalidAuthority.into());
+    }
+    if config.sealed {
+        return Err(AppError::CollectionSealed.into());
+    }
+    Ok(config)
+}
+
+/// Refuses changes to an NFT minted under a sealed config. The record PDA
+/// follows from the mint, so it can't be swapped out; NFTs without a record
+/// weren't minted by this program and belong to no config.
+///
+/// Holder token operations (Transfer, TransferNft, Delegate, RevokeDelegate
+/// and closing token accounts) skip this check: a seal locks the drop, not
+/// what holders do with the NFTs they own.
+pub fn assert_not_sealed(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    record_info: &AccountInfo,
+    config_info: &AccountInfo,
+) -> ProgramResult {
+    assert_derivation(program_id, record_info, &[RECORD_SEED, mint.as_ref()])?;
+    if record_info.owner != program_id || record_info.data_is_empty() {
+        return Ok(());
+    }
+    let record = MintRecord::from_account_info(program_id, record_info)?;
+    if record.config != *config_info.key {
+        msg!("Mint {} belongs to config {}", mint, record.config);
+        return Err(AppError::InvalidAuthority.into());
+    }
+    if CollectionConfig::from_account_info(program_id, config_info)?.sealed {
+        return Err(AppError::CollectionSealed.into());
+    }
+    Ok(())
+}
+
+pub fn process_set_price(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    price_lamports: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    msg!("Set Price {}", price_lamports);
+    config.price_lamports = price_lamports;
+    config.save(config_info)
+}
+
+pub fn process_add_delegate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delegate: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    if config.delegates.contains(&delegate) {
+        msg!("Delegate {} already added", delegate);
+        return Ok(());
+    }
+    if config.delegates.len() >= CollectionConfig::MAX_DELEGATES {
+        return Err(AppError::TooManyDelegates.into());
+    }
+    msg!("Add Delegate {}", delegate);
+    config.delegates.push(delegate);
+    config.save(config_info)
+}
+
+pub fn process_remove_delegate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delegate: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    let len = config.delegates.len();
+    config.delegates.retain(|d| *d != delegate);
+    if config.delegates.len() == len {
+        return Err(AppError::NotADelegate.into());
+    }
+    msg!("Remove Delegate {}", delegate);
+    config.save(config_info)
+}
+
+pub fn process_set_token_price(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    payment_mint: Option<Pubkey>,
+    price_tokens: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    msg!("Set Token Price {:?} {}", payment_mint, price_tokens);
+    config.payment_mint = payment_mint;
+    config.price_tokens = price_tokens;
+    config.save(config_info)
+}
+
+pub fn process_set_uri_template(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    uri_template: Option<String>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    if let Some(template) = &uri_template {
+        validate_uri_template(template)?;
+    }
+    msg!("Set Uri Template {:?}", uri_template);
+    config.uri_template = uri_template;
+    config.save(config_info)
+}
+
+pub fn process_set_mint_window(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    start: u64,
+    end: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    assert_mint_window(start, end)?;
+    msg!("Set Mint Window {} {}", start, end);
+    config.mint_start = start;
+    config.mint_end = end;
+    config.save(config_info)
+}
+
+pub fn process_set_max_per_wallet(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_per_wallet: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    msg!("Set Max Per Wallet {}", max_per_wallet);
+    config.max_per_wallet = max_per_wallet;
+    config.save(config_info)
+}
+
+/// 0 lifts the bound on its side, two real bounds need start < end.
+fn assert_mint_window(start: u64, end: u64) -> ProgramResult {
+    if start != 0 && end != 0 && start >= end {
+        return Err(AppError::InvalidMintWindow.into());
+    }
+    Ok(())
+}
+
+pub fn process_update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: UpdateConfigArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    let UpdateConfigArgs {
+        price_lamports,
+        treasury,
+        is_paused,
+        mint_start,
+        mint_end,
+        randomized,
+        protocol_fee_bps,
+        fee_recipient,
+        default_creators,
+    } = args;
+    let mint_start = mint_start.unwrap_or(config.mint_start);
+    let mint_end = mint_end.unwrap_or(config.mint_end);
+    assert_mint_window(mint_start, mint_end)?;
+    let protocol_fee_bps = protocol_fee_bps.unwrap_or(config.protocol_fee_bps);
+    if protocol_fee_bps > 10_000 {
+        return Err(AppError::InvalidFeeBps.into());
+    }
+    // checked here so a bad list fails the update, not every later mint
+    if let Some(default_creators) = &default_creators {
+        if !default_creators.is_empty() {
+            validate_creators(default_creators)?;
+        }
+    }
+
+    msg!("Update Config");
+    config.price_lamports = price_lamports.unwrap_or(config.price_lamports);
+    config.treasury = treasury.unwrap_or(config.treasury);
+    config.is_paused = is_paused.unwrap_or(config.is_paused);
+    config.mint_start = mint_start;
+    config.mint_end = mint_end;
+    config.randomized = randomized.unwrap_or(config.randomized);
+    config.protocol_fee_bps = protocol_fee_bps;
+    config.fee_recipient = fee_recipient.unwrap_or(config.fee_recipient);
+    if let Some(default_creators) = default_creators {
+        config.default_creators = default_creators;
+    }
+    config.save(config_info)
+}
+
+pub fn process_update_merkle_root(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_root: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    msg!("Update Merkle Root");
+    config.merkle_root = new_root;
+    config.save(config_info)
+}
+
+/// Decommissions a finished drop: the supply is capped at what was minted,
+/// the allowlist phases are turned off and the config is sealed, so every
+/// later admin instruction, mint and metadata change fails. Burns are
+/// unaffected. The collection metadata, if any, is made immutable first.
+pub fn process_seal_collection(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let mint_authority = optional_account(program_id, account_info_iter)?;
+    let collection_metadata = optional_account(program_id, account_info_iter)?;
+    let metadata_program = optional_account(program_id, account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    if let Some(collection_mint) = config.collection_mint {
+        let (mint_authority_info, collection_metadata_info, metadata_program_info) =
+            match (mint_authority, collection_metadata, metadata_program) {
+                (Some(authority), Some(metadata), Some(program)) => (authority, metadata, program),
+                _ => {
+                    msg!("Sealing needs the collection metadata accounts");
+                    return Err(ProgramError::NotEnoughAccountKeys);
+                }
+            };
+        let authority_bump = assert_mint_authority(program_id, mint_authority_info)?;
+        assert_metadata_program(metadata_program_info)?;
+        let metadata_program_id = mpl_token_metadata::id();
+        assert_derivation(
+            &metadata_program_id,
+            collection_metadata_info,
+            &[PREFIX.as_bytes(), metadata_program_id.as_ref(), collection_mint.as_ref()],
+        )?;
+
+        msg!("Make Collection Metadata Immutable");
+        let authority_seeds: &[&[u8]] =
+            &[MINT_AUTHORITY_SEED, program_id.as_ref(), &[authority_bump]];
+        invoke_signed(
+            &update_metadata_accounts_v2(
+                *metadata_program_info.key,
+                *collection_metadata_info.key,
+                *mint_authority_info.key,
+                None,
+                None,
+                None,
+                Some(false),
+            ),
+            &[
+                collection_metadata_info.clone(),
+                mint_authority_info.clone(),
+                metadata_program_info.clone(),
+            ],
+            &[authority_seeds],
+        )?;
+    }
+
+    msg!("Seal Collection at {}", config.minted);
+    config.max_supply = config.minted;
+    config.whitelist_enabled = false;
+    config.merkle_root = [0; 32];
+    config.sealed = true;
+    config.save(config_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instruction::CreatorArgs,
+        processor::{process_burn, process_delegate, process_mint, process_update_uri},
+        test_utils::*,
+    };
+    use mpl_token_metadata::{instruction::MetadataInstruction, pda::find_metadata_account};
+
+    #[test]
+    fn update_config_sets_the_protocol_fee() {
+        setup();
+        let authority = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::wallet(authority).signer(),
+            config_account(&test_config(authority)),
+        ];
+        let args = UpdateConfigArgs { protocol_fee_bps: Some(10_000), ..Default::default() };
+        process_update_config(&crate::id(), &infos(&mut accounts), args).unwrap();
+        assert_eq!(load_config(&accounts[1]).protocol_fee_bps, 10_000);
+    }
+
+    #[test]
+    fn update_config_rejects_invalid_default_creators() {
+        setup();
+        let authority = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::wallet(authority).signer(),
+            config_account(&test_config(authority)),
+        ];
+        let creator = CreatorArgs { address: authority, share: 50, provenance_only: false };
+        let args = UpdateConfigArgs {
+            default_creators: Some(vec![creator.clone(), creator]),
+            ..Default::default()
+        };
+        assert_eq!(
+            process_update_config(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::DuplicateCreator.into())
+        );
+        assert!(load_config(&accounts[1]).default_creators.is_empty());
+    }
+
+    #[test]
+    fn update_config_sets_and_clears_default_creators() {
+        setup();
+        let authority = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::wallet(authority).signer(),
+            config_account(&test_config(authority)),
+        ];
+        let creators = vec![CreatorArgs { address: authority, share: 100, provenance_only: false }];
+        let args = UpdateConfigArgs {
+            default_creators: Some(creators.clone()),
+            ..Default::default()
+        };
+        process_update_config(&crate::id(), &infos(&mut accounts), args).unwrap();
+        assert_eq!(load_config(&accounts[1]).default_creators, creators);
+
+        let args = UpdateConfigArgs { default_creators: Some(vec![]), ..Default::default() };
+        process_update_config(&crate::id(), &infos(&mut accounts), args).unwrap();
+        assert!(load_config(&accounts[1]).default_creators.is_empty());
+    }
+
+    #[test]
+    fn update_config_rejects_a_fee_above_10000_bps() {
+        setup();
+        let authority = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::wallet(authority).signer(),
+            config_account(&test_config(authority)),
+        ];
+        let args = UpdateConfigArgs { protocol_fee_bps: Some(10_001), ..Default::default() };
+        assert_eq!(
+            process_update_config(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::InvalidFeeBps.into())
+        );
+    }
+
+    #[test]
+    fn not_sealed_passes_nfts_without_a_record() {
+        let mint = Pubkey::new_unique();
+        let [mut record, mut config] = unrecorded(&mint);
+        assert_eq!(assert_not_sealed(&crate::id(), &mint, &record.info(), &config.info()), Ok(()));
+    }
+
+    #[test]
+    fn not_sealed_checks_the_config_of_the_record() {
+        let mint = Pubkey::new_unique();
+        let mut config = test_config(Pubkey::new_unique());
+        let [mut record, mut open] = recorded(&mint, &config);
+        assert_eq!(assert_not_sealed(&crate::id(), &mint, &record.info(), &open.info()), Ok(()));
+
+        config.sealed = true;
+        let [_, mut sealed] = recorded(&mint, &config);
+        assert_eq!(
+            assert_not_sealed(&crate::id(), &mint, &record.info(), &sealed.info()),
+            Err(AppError::CollectionSealed.into())
+        );
+
+        let mut other = config_account(&test_config(Pubkey::new_unique()));
+        assert_eq!(
+            assert_not_sealed(&crate::id(), &mint, &record.info(), &other.info()),
+            Err(AppError::InvalidAuthority.into())
+        );
+    }
+
+    #[test]
+    fn not_sealed_refuses_the_record_of_another_mint() {
+        let config = test_config(Pubkey::new_unique());
+        let [mut record, mut config] = recorded(&Pubkey::new_unique(), &config);
+        assert_eq!(
+            assert_not_sealed(&crate::id(), &Pubkey::new_unique(), &record.info(), &config.info()),
+            Err(AppError::InvalidDerivedKey.into())
+        );
+    }
+
+    /// SealCollection accounts of `config` without a collection.
+    fn seal_accounts(config: &CollectionConfig) -> Vec<TestAccount> {
+        vec![
+            TestAccount::wallet(config.authority).signer(),
+            config_account(config),
+            absent(),
+            absent(),
+            absent(),
+        ]
+    }
+
+    #[test]
+    fn seal_makes_the_collection_metadata_immutable() {
+        setup();
+        let collection_mint = Pubkey::new_unique();
+        let mut config = test_config(Pubkey::new_unique());
+        config.collection_mint = Some(collection_mint);
+        let mut accounts = seal_accounts(&config);
+        accounts[2] = TestAccount::new(
+            pda(&[MINT_AUTHORITY_SEED, crate::id().as_ref()]),
+            crate::id(),
+            vec![],
+        );
+        accounts[3] = TestAccount::empty(find_metadata_account(&collection_mint).0);
+        accounts[4] = TestAccount::program(mpl_token_metadata::id());
+        process_seal_collection(&crate::id(), &infos(&mut accounts)).unwrap();
+
+        let calls = metadata_calls();
+        assert!(matches!(
+            &calls[..],
+            [MetadataInstruction::UpdateMetadataAccountV2(args)]
+                if args.is_mutable == Some(false) && args.data.is_none()
+        ));
+        assert_eq!(invoked()[0].signers, 1);
+        assert!(load_config(&accounts[1]).sealed);
+    }
+
+    #[test]
+    fn seal_needs_the_collection_accounts_of_a_collection() {
+        setup();
+        let mut config = test_config(Pubkey::new_unique());
+        config.collection_mint = Some(Pubkey::new_unique());
+        let mut accounts = seal_accounts(&config);
+        assert_eq!(
+            process_seal_collection(&crate::id(), &infos(&mut accounts)),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn sealed_collection_locks_mints_and_metadata_but_not_holders() {
+        setup();
+        let config = test_config(Pubkey::new_unique());
+        let signer = Pubkey::new_unique();
+        let mut mint_accounts = mint_accounts(&config, signer);
+        let mint = mint_accounts[mint_slot::MINT].key;
+        process_mint(&crate::id(), &infos(&mut mint_accounts), test_mint_args(mint)).unwrap();
+
+        let mut accounts = seal_accounts(&config);
+        accounts[1] = mint_accounts[mint_slot::CONFIG].clone();
+        process_seal_collection(&crate::id(), &infos(&mut accounts)).unwrap();
+        let sealed = load_config(&accounts[1]);
+        assert!(sealed.sealed);
+        assert_eq!(sealed.max_supply, 1);
+        mint_accounts[mint_slot::CONFIG] = accounts[1].clone();
+
+        setup();
+        let next = Pubkey::new_unique();
+        let mut next_accounts = mint_accounts.clone();
+        set_mint(&mut next_accounts, next);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut next_accounts), test_mint_args(next)),
+            Err(AppError::CollectionSealed.into())
+        );
+
+        let mut update_accounts = vec![
+            TestAccount::wallet(signer).signer(),
+            metadata_account(&test_metadata(mint, signer)),
+            TestAccount::program(mpl_token_metadata::id()),
+            mint_accounts[mint_slot::RECORD].clone(),
+            mint_accounts[mint_slot::CONFIG].clone(),
+        ];
+        assert_eq!(
+            process_update_uri(
+                &crate::id(),
+                &infos(&mut update_accounts),
+                String::from("https://example.com/new.json"),
+            ),
+            Err(AppError::CollectionSealed.into())
+        );
+
+        // holders keep using their tokens
+        let delegate = Pubkey::new_unique();
+        let mut delegate_accounts = vec![
+            TestAccount::wallet(signer).signer(),
+            mint_accounts[mint_slot::ATA].clone(),
+            TestAccount::wallet(delegate),
+            TestAccount::program(spl_token::id()),
+        ];
+        process_delegate(&crate::id(), &infos(&mut delegate_accounts), delegate).unwrap();
+
+        let mut burn_accounts = vec![
+            TestAccount::wallet(signer).signer(),
+            mint_accounts[mint_slot::MINT].clone(),
+            mint_accounts[mint_slot::ATA].clone(),
+            metadata_account(&test_metadata(mint, signer)),
+            mint_accounts[mint_slot::EDITION].clone(),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(mpl_token_metadata::id()),
+            absent(),
+        ];
+        process_burn(&crate::id(), &infos(&mut burn_accounts)).unwrap();
+        assert!(matches!(metadata_calls()[..], [MetadataInstruction::BurnNft]));
+    }
+}
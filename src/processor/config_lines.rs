@@ -0,0 +1,168 @@
+use borsh::BorshSerialize;
+use mpl_token_metadata::state::{MAX_NAME_LENGTH, MAX_URI_LENGTH};
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{
+    error::AppError,
+    processor::{config::assert_config_authority, mint::MintParams},
+    state::{CollectionConfig, ConfigLine, ConfigLinesHeader, CONFIG_LINES_SEED, STATE_VERSION},
+    utils::*,
+};
+
+pub fn process_add_config_lines(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lines: Vec<(String, String)>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let lines_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_info = next_account_info(account_info_iter)?;
+
+    assert_config_authority(program_id, config_info, authority_info)?;
+    let bump = assert_derivation(
+        program_id,
+        lines_info,
+        &[CONFIG_LINES_SEED, config_info.key.as_ref()],
+    )?;
+    for (name, uri) in &lines {
+        if name.len() > MAX_NAME_LENGTH {
+            return Err(AppError::NameTooLong.into());
+        }
+        if uri.is_empty() {
+            return Err(AppError::InvalidUri.into());
+        }
+        if uri.len() > MAX_URI_LENGTH {
+            return Err(AppError::UriTooLong.into());
+        }
+    }
+
+    let mut header = if lines_info.data_is_empty() {
+        msg!("Create Config Lines");
+        create_or_allocate_account_raw(
+            *program_id,
+            lines_info,
+            rent_info,
+            system_info,
+            authority_info,
+            ConfigLinesHeader::LEN,
+            &[CONFIG_LINES_SEED, config_info.key.as_ref(), &[bump]],
+        )?;
+        ConfigLinesHeader {
+            total: 0,
+            remaining: 0,
+            bump,
+            version: STATE_VERSION,
+        }
+    } else {
+        ConfigLinesHeader::from_account_info(program_id, lines_info)?
+    };
+
+    let added = lines.len() as u32;
+    let total = header
+        .total
+        .checked_add(added)
+        .ok_or(AppError::NumericalOverflow)?;
+    let new_len = ConfigLine::offset(total);
+    let rent = Rent::from_account_info(rent_info)?;
+    let missing = rent
+        .minimum_balance(new_len)
+        .saturating_sub(lines_info.lamports());
+    if missing > 0 {
+        invoke(
+            &system_instruction::transfer(authority_info.key, lines_info.key, missing),
+            &[authority_info.clone(), lines_info.clone(), system_info.clone()],
+        )?;
+    }
+    lines_info.realloc(new_len, true)?;
+
+    msg!("Add Config Lines {}", added);
+    {
+        let mut data = lines_info.data.borrow_mut();
+        for (index, (name, uri)) in (header.total..total).zip(lines) {
+            let line = &mut data[ConfigLine::offset(index)..ConfigLine::offset(index + 1)];
+            line[ConfigLine::NAME_OFFSET..ConfigLine::NAME_OFFSET + name.len()]
+                .copy_from_slice(name.as_bytes());
+            line[ConfigLine::URI_OFFSET..ConfigLine::URI_OFFSET + uri.len()]
+                .copy_from_slice(uri.as_bytes());
+        }
+    }
+    header.total = total;
+    header.remaining = header
+        .remaining
+        .checked_add(added)
+        .ok_or(AppError::NumericalOverflow)?;
+    header.serialize(&mut *lines_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Replaces the name and uri of `params` with a random unconsumed config line
+/// and marks that line consumed. The index is drawn over the lines still
+/// remaining, then the consumed ones are skipped, so every remaining line is
+/// equally likely.
+pub fn draw_config_line(
+    program_id: &Pubkey,
+    config: &CollectionConfig,
+    config_info: &AccountInfo,
+    lines_info: Option<&AccountInfo>,
+    slot_hashes_info: Option<&AccountInfo>,
+    params: &mut MintParams,
+    nonce: &[u8],
+) -> ProgramResult {
+    if !config.randomized {
+        return Ok(());
+    }
+    let lines_info = lines_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let slot_hashes_info = slot_hashes_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    assert_owned_by(lines_info, program_id)?;
+    assert_derivation(
+        program_id,
+        lines_info,
+        &[CONFIG_LINES_SEED, config_info.key.as_ref()],
+    )?;
+
+    let mut header = ConfigLinesHeader::from_account_info(program_id, lines_info)?;
+    let mut data = lines_info.data.borrow_mut();
+    if header.remaining == 0 {
+        return Err(AppError::NoLinesRemaining.into());
+    }
+    let mut skip = get_random_range(slot_hashes_info, nonce, u64::from(header.remaining))?;
+    let mut picked = None;
+    for index in 0..header.total {
+        let offset = ConfigLine::offset(index);
+        if data[offset] != 0 {
+            continue;
+        }
+        if skip == 0 {
+            picked = Some(offset);
+            break;
+        }
+        skip -= 1;
+    }
+    let offset = picked.ok_or(AppError::NoLinesRemaining)?;
+
+    let line = &mut data[offset..offset + ConfigLine::LEN];
+    line[0] = 1;
+    params.title = read_padded(&line[ConfigLine::NAME_OFFSET..ConfigLine::URI_OFFSET]);
+    params.uri = read_padded(&line[ConfigLine::URI_OFFSET..]);
+    msg!("Draw Config Line {}", params.uri);
+
+    header.remaining -= 1;
+    header.serialize(&mut &mut data[..ConfigLinesHeader::LEN])?;
+    Ok(())
+}
+
+fn read_padded(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string()
+}
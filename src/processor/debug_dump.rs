@@ -0,0 +1,148 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::set_return_data,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    state::{
+        CONFIG_LINES_SEED, CONFIG_SEED, MINT_AUTHORITY_SEED, RECORD_SEED, WALLET_COUNTER_SEED,
+        WHITELIST_SEED,
+    },
+};
+
+pub const MAX_DUMP_ACCOUNTS: usize = 10;
+
+/// Which of our PDAs an account is, for the context given to DebugDump.
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum KnownPda {
+    None,
+    MintAuthority,
+    Config,
+    MintRecord,
+    WhitelistEntry,
+    WalletCounter,
+    ConfigLines,
+}
+
+/// 46 bytes per account once serialized, so ten of them stay well under the
+/// return data limit.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct AccountSummary {
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data_len: u32,
+    /// first data byte, 0 for an empty account
+    pub discriminator: u8,
+    pub known_pda: KnownPda,
+}
+
+pub fn process_debug_dump(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    authority: Option<Pubkey>,
+    wallet: Option<Pubkey>,
+    mint: Option<Pubkey>,
+) -> ProgramResult {
+    if accounts.len() > MAX_DUMP_ACCOUNTS {
+        return Err(AppError::BatchTooLarge.into());
+    }
+
+    // derive every PDA the context allows once, then match accounts against them
+    let derive = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, program_id).0;
+    let mut known = vec![(
+        derive(&[MINT_AUTHORITY_SEED, program_id.as_ref()]),
+        KnownPda::MintAuthority,
+    )];
+    if let Some(authority) = authority {
+        let config = derive(&[CONFIG_SEED, authority.as_ref()]);
+        known.push((config, KnownPda::Config));
+        known.push((derive(&[CONFIG_LINES_SEED, config.as_ref()]), KnownPda::ConfigLines));
+        if let Some(wallet) = wallet {
+            known.push((
+                derive(&[WHITELIST_SEED, config.as_ref(), wallet.as_ref()]),
+                KnownPda::WhitelistEntry,
+            ));
+            known.push((
+                derive(&[WALLET_COUNTER_SEED, config.as_ref(), wallet.as_ref()]),
+                KnownPda::WalletCounter,
+            ));
+        }
+    }
+    if let Some(mint) = mint {
+        known.push((derive(&[RECORD_SEED, mint.as_ref()]), KnownPda::MintRecord));
+    }
+
+    let summaries: Vec<AccountSummary> = accounts
+        .iter()
+        .map(|account_info| AccountSummary {
+            owner: *account_info.owner,
+            lamports: account_info.lamports(),
+            data_len: account_info.data_len() as u32,
+            discriminator: account_info.data.borrow().first().copied().unwrap_or(0),
+            known_pda: known
+                .iter()
+                .find(|(key, _)| key == account_info.key)
+                .map_or(KnownPda::None, |(_, pda)| *pda),
+        })
+        .collect();
+
+    msg!("Debug Dump {}", summaries.len());
+    set_return_data(&summaries.try_to_vec()?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn debug_dump_recognizes_every_pda_of_the_context() {
+        setup();
+        let (authority, wallet, mint) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let config = pda(&[CONFIG_SEED, authority.as_ref()]);
+        let mut accounts: Vec<_> = [
+            pda(&[MINT_AUTHORITY_SEED, crate::id().as_ref()]),
+            config,
+            pda(&[RECORD_SEED, mint.as_ref()]),
+            pda(&[WHITELIST_SEED, config.as_ref(), wallet.as_ref()]),
+            pda(&[WALLET_COUNTER_SEED, config.as_ref(), wallet.as_ref()]),
+            pda(&[CONFIG_LINES_SEED, config.as_ref()]),
+            wallet,
+        ]
+        .into_iter()
+        .map(TestAccount::wallet)
+        .collect();
+        process_debug_dump(
+            &crate::id(),
+            &infos(&mut accounts),
+            Some(authority),
+            Some(wallet),
+            Some(mint),
+        )
+        .unwrap();
+
+        let summaries = Vec::<AccountSummary>::try_from_slice(&return_data().unwrap()).unwrap();
+        let known: Vec<_> = summaries.iter().map(|summary| summary.known_pda).collect();
+        assert_eq!(
+            known,
+            vec![
+                KnownPda::MintAuthority,
+                KnownPda::Config,
+                KnownPda::MintRecord,
+                KnownPda::WhitelistEntry,
+                KnownPda::WalletCounter,
+                KnownPda::ConfigLines,
+                KnownPda::None,
+            ]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use spl_token::instruction::{approve, revoke};
+use spl_token_2022::instruction as token_2022;
+
+use crate::{error::AppError, utils::*};
+
+/// Lets `delegate`, e.g. a marketplace, move the signer's NFT. Like the
+/// other holder token operations it is allowed after a seal.
+pub fn process_delegate(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delegate: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let ata_info = next_account_info(account_info_iter)?;
+    let delegate_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    assert_eq_pubkey(delegate_info, &delegate)?;
+    let token_program = assert_held_nft(owner_info, ata_info, token_program_info)?;
+
+    msg!("Approve {}", delegate);
+    let (ata, delegate, owner) = (ata_info.key, delegate_info.key, owner_info.key);
+    invoke(
+        &match token_program {
+            TokenProgram::Legacy(_) => approve(token_program.id(), ata, delegate, owner, &[], 1)?,
+            TokenProgram::Token2022(_) => {
+                token_2022::approve(token_program.id(), ata, delegate, owner, &[], 1)?
+            }
+        },
+        &[
+            ata_info.clone(),
+            delegate_info.clone(),
+            owner_info.clone(),
+            token_program_info.clone(),
+        ],
+    )
+}
+
+/// Undoes a Delegate, also after a seal.
+pub fn process_revoke_delegate(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let ata_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    let token_program = assert_held_nft(owner_info, ata_info, token_program_info)?;
+
+    msg!("Revoke");
+    let (ata, owner) = (ata_info.key, owner_info.key);
+    invoke(
+        &match token_program {
+            TokenProgram::Legacy(_) => revoke(token_program.id(), ata, owner, &[])?,
+            TokenProgram::Token2022(_) => token_2022::revoke(token_program.id(), ata, owner, &[])?,
+        },
+        &[ata_info.clone(), owner_info.clone(), token_program_info.clone()],
+    )
+}
+
+/// The token account must belong to the signer and hold exactly one NFT.
+fn assert_held_nft<'a>(
+    owner_info: &AccountInfo,
+    ata_info: &AccountInfo,
+    token_program_info: &AccountInfo<'a>,
+) -> Result<TokenProgram<'a>, ProgramError> {
+    let token_program = TokenProgram::from_account_info(token_program_info)
+        .map_err(|_| AppError::InvalidProgramId)?;
+    let ata = assert_token_account_owner(&token_program, ata_info, owner_info.key)?;
+    if ata.amount != 1 {
+        return Err(AppError::InvalidTokenAmount.into());
+    }
+    Ok(token_program)
+}
@@ -0,0 +1,224 @@
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+use spl_token::{
+    instruction::{freeze_account, thaw_account},
+    state::AccountState,
+};
+use spl_token_2022::instruction as token_2022;
+
+use crate::{
+    error::AppError,
+    processor::{config::assert_config_authority, mint::assert_mint_authority},
+    state::{MintRecord, MINT_AUTHORITY_SEED, RECORD_SEED},
+    utils::*,
+};
+
+pub fn process_freeze(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    set_frozen(program_id, accounts, true)
+}
+
+pub fn process_thaw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    set_frozen(program_id, accounts, false)
+}
+
+/// Freezes or thaws a holder's token account with the mint authority PDA,
+/// which every mint of this program has as freeze authority. That PDA is
+/// shared by all configs, so the mint record must name the signer's config.
+fn set_frozen(program_id: &Pubkey, accounts: &[AccountInfo], freeze: bool) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let ata_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_config_authority(program_id, config_info, config_authority_info)?;
+    let authority_bump = assert_mint_authority(program_id, authority_info)?;
+    assert_derivation(program_id, record_info, &[RECORD_SEED, mint_info.key.as_ref()])?;
+    let record = MintRecord::from_account_info(program_id, record_info)?;
+    if record.config != *config_info.key {
+        msg!("Mint {} belongs to config {}", mint_info.key, record.config);
+        return Err(AppError::InvalidAuthority.into());
+    }
+    let token_program = TokenProgram::from_account_info(token_program_info)?;
+    let ata = assert_token_account_mint(&token_program, ata_info, mint_info.key)?;
+    match (freeze, ata.state) {
+        (true, AccountState::Frozen) => return Err(AppError::AccountAlreadyFrozen.into()),
+        (false, AccountState::Initialized) => return Err(AppError::AccountNotFrozen.into()),
+        _ => {}
+    }
+
+    freeze_token_account(
+        program_id,
+        authority_info,
+        mint_info,
+        ata_info,
+        &token_program,
+        authority_bump,
+        freeze,
+    )
+}
+
+pub fn freeze_token_account<'a>(
+    program_id: &Pubkey,
+    authority_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    ata_info: &AccountInfo<'a>,
+    token_program: &TokenProgram<'a>,
+    authority_bump: u8,
+    freeze: bool,
+) -> ProgramResult {
+    let authority_seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, program_id.as_ref(), &[authority_bump]];
+    let token_program_info = token_program.account_info();
+    let (ata, mint, authority) = (ata_info.key, mint_info.key, authority_info.key);
+    let instruction = match (freeze, token_program) {
+        (true, TokenProgram::Legacy(_)) => {
+            msg!("Freeze Account");
+            freeze_account(token_program_info.key, ata, mint, authority, &[])?
+        }
+        (true, TokenProgram::Token2022(_)) => {
+            msg!("Freeze Account");
+            token_2022::freeze_account(token_program_info.key, ata, mint, authority, &[])?
+        }
+        (false, TokenProgram::Legacy(_)) => {
+            msg!("Thaw Account");
+            thaw_account(token_program_info.key, ata, mint, authority, &[])?
+        }
+        (false, TokenProgram::Token2022(_)) => {
+            msg!("Thaw Account");
+            token_2022::thaw_account(token_program_info.key, ata, mint, authority, &[])?
+        }
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            ata_info.clone(),
+            mint_info.clone(),
+            authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[authority_seeds],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program::program_error::ProgramError;
+
+    use super::*;
+    use crate::{state::STATE_VERSION, test_utils::*};
+
+    /// Accounts of a Freeze by the authority of a fresh config, for a mint
+    /// whose record names `record_config`.
+    fn freeze_accounts(record_config: Option<Pubkey>) -> Vec<TestAccount> {
+        let config_authority = Pubkey::new_unique();
+        let config = config_account(&test_config(config_authority));
+        let authority = pda(&[MINT_AUTHORITY_SEED, crate::id().as_ref()]);
+        let mint = Pubkey::new_unique();
+        let record = MintRecord {
+            mint,
+            minter: Pubkey::new_unique(),
+            config: record_config.unwrap_or(config.key),
+            slot: 1,
+            unix_timestamp: 1,
+            bump: 0,
+            version: STATE_VERSION,
+        };
+        vec![
+            TestAccount::wallet(config_authority).signer(),
+            config,
+            TestAccount::new(authority, crate::id(), vec![]),
+            TestAccount::mint(mint, &spl_token::id(), &authority, 0),
+            TestAccount::state(
+                pda(&[RECORD_SEED, mint.as_ref()]),
+                crate::id(),
+                &record,
+                MintRecord::LEN,
+            ),
+            TestAccount::token_account(
+                Pubkey::new_unique(),
+                &spl_token::id(),
+                &mint,
+                &Pubkey::new_unique(),
+                1,
+            ),
+            TestAccount::program(spl_token::id()),
+        ]
+    }
+
+    #[test]
+    fn freeze_signs_with_the_mint_authority() {
+        setup();
+        let mut accounts = freeze_accounts(None);
+        process_freeze(&crate::id(), &infos(&mut accounts)).unwrap();
+
+        let calls = invoked_program(&spl_token::id());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].signers, 1);
+        assert_eq!(calls[0].instruction.accounts[0].pubkey, accounts[5].key);
+    }
+
+    #[test]
+    fn freeze_builds_a_token_2022_instruction_for_a_token_2022_account() {
+        setup();
+        let mut accounts = freeze_accounts(None);
+        let (mint, owner) = (accounts[3].key, Pubkey::new_unique());
+        accounts[5] =
+            TestAccount::token_account(accounts[5].key, &spl_token_2022::id(), &mint, &owner, 1);
+        accounts[6] = TestAccount::program(spl_token_2022::id());
+        process_freeze(&crate::id(), &infos(&mut accounts)).unwrap();
+
+        assert_eq!(invoked_program(&spl_token_2022::id()).len(), 1);
+        assert!(invoked_program(&spl_token::id()).is_empty());
+    }
+
+    #[test]
+    fn freeze_rejects_an_account_of_another_token_program() {
+        setup();
+        let mut accounts = freeze_accounts(None);
+        accounts[6] = TestAccount::program(spl_token_2022::id());
+        assert_eq!(
+            process_freeze(&crate::id(), &infos(&mut accounts)),
+            Err(AppError::InvalidOwner.into())
+        );
+    }
+
+    #[test]
+    fn freeze_rejects_a_mint_of_another_config() {
+        setup();
+        let mut accounts = freeze_accounts(Some(Pubkey::new_unique()));
+        assert_eq!(
+            process_freeze(&crate::id(), &infos(&mut accounts)),
+            Err(AppError::InvalidAuthority.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn freeze_rejects_a_record_of_another_mint() {
+        setup();
+        let mut accounts = freeze_accounts(None);
+        accounts[3].key = Pubkey::new_unique();
+        assert_eq!(
+            process_freeze(&crate::id(), &infos(&mut accounts)),
+            Err(AppError::InvalidDerivedKey.into())
+        );
+    }
+
+    #[test]
+    fn thaw_rejects_an_account_that_is_not_frozen() {
+        setup();
+        let mut accounts = freeze_accounts(None);
+        assert_eq!(
+            process_thaw(&crate::id(), &infos(&mut accounts)),
+            Err(ProgramError::from(AppError::AccountNotFrozen))
+        );
+    }
+}
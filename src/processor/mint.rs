@@ -1,23 +1,145 @@
-use mpl_token_metadata::instruction::{create_master_edition_v3, create_metadata_accounts_v2};
+use mpl_token_metadata::{
+    instruction::{
+        create_master_edition_v3, create_metadata_accounts_v3, update_metadata_accounts_v2,
+    },
+    state::{
+        Collection, CollectionDetails, Creator, EDITION, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH,
+        MAX_URI_LENGTH, PREFIX,
+    },
+};
+use borsh::BorshSerialize;
 use solana_program::{
     account_info::{AccountInfo, next_account_info},
     entrypoint::ProgramResult,
+    hash::hash,
     msg,
-    program::invoke,
+    program::{invoke, invoke_signed, set_return_data},
+    program_error::ProgramError,
     pubkey::Pubkey,
-    system_instruction,
-    sysvar::{rent::Rent, Sysvar},
+    system_instruction, system_program,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+use spl_token::state::Mint;
+
+use crate::{
+    error::AppError,
+    instruction::{CreatorArgs, MintNftArgs},
+    processor::{
+        collection::{assert_config_collection, verify_collection_item, CollectionAccounts},
+        config_lines::draw_config_line,
+        freeze::freeze_token_account,
+        session::use_session,
+        whitelist::consume_whitelist,
+    },
+    state::{
+        CollectionConfig, MintRecord, WalletCounter, CONFIG_SEED, MINT_AUTHORITY_SEED, RECORD_SEED,
+        STATE_VERSION, WALLET_COUNTER_SEED,
+    },
+    utils::*,
 };
-use spl_associated_token_account::instruction::create_associated_token_account;
-use spl_token::instruction::{initialize_mint, mint_to};
 
-use crate::{utils::*};
+/// Metadata of one NFT, validated before any account is touched.
+pub struct MintParams {
+    pub title: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub mutable: bool,
+    pub max_supply: Option<u64>,
+    pub decimals: u8,
+    pub amount: u64,
+    pub collection: Option<Collection>,
+    pub collection_details: Option<CollectionDetails>,
+    pub soulbound: bool,
+    pub trait_options: Vec<u8>,
+    pub update_authority: Option<Pubkey>,
+    /// wallet the mint record names: the signer, or the player a session key
+    /// mints for
+    pub minter: Pubkey,
+}
+
+impl MintParams {
+    /// Only a single indivisible token gets a master edition.
+    pub fn is_edition(&self) -> bool {
+        self.decimals == 0 && self.amount == 1
+    }
+}
+
+/// Randomized traits a single mint may ask for.
+pub const MAX_TRAITS: usize = 8;
+
+/// Accounts shared by every NFT minted in one instruction.
+pub struct MintAccounts<'a, 'b> {
+    /// program PDA holding the mint and freeze authority
+    pub authority_info: &'a AccountInfo<'b>,
+    pub signer_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub ass_token_program_info: &'a AccountInfo<'b>,
+    pub rent_info: &'a AccountInfo<'b>,
+    pub system_info: &'a AccountInfo<'b>,
+    pub metadata_program_info: &'a AccountInfo<'b>,
+}
+
+/// Accounts created for a single NFT.
+pub struct NftAccounts<'a, 'b> {
+    pub mint_info: &'a AccountInfo<'b>,
+    pub ata_info: &'a AccountInfo<'b>,
+    pub metadata_info: &'a AccountInfo<'b>,
+    pub edition_info: &'a AccountInfo<'b>,
+    pub record_info: &'a AccountInfo<'b>,
+    /// wallet the token account is created for, the signer unless given
+    pub recipient_info: &'a AccountInfo<'b>,
+}
 
 pub fn process_mint(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
-    title: Option<String>,
-    uri: String
+    args: MintNftArgs,
+) -> ProgramResult {
+    process_public_mint(program_id, accounts, args, None)
+}
+
+/// Mint gated on a merkle proof that the signer is in the config's allowlist.
+pub fn process_mint_with_proof(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: MintNftArgs,
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    process_public_mint(program_id, accounts, args, Some(proof))
+}
+
+/// The rules a single mint is held to besides the supply, the collection and
+/// the randomized draws, which apply to every mint.
+pub enum MintKind<'a, 'b> {
+    /// the signer mints for `minter_info`, itself or the player of its
+    /// session: pause, window, allowlist and wallet limit apply to the
+    /// minter, the signer pays the price
+    Public {
+        minter_info: &'a AccountInfo<'b>,
+        proof: Option<Vec<[u8; 32]>>,
+        whitelist_info: Option<&'a AccountInfo<'b>>,
+        counter_info: Option<&'a AccountInfo<'b>>,
+        treasury_info: &'a AccountInfo<'b>,
+        fee_recipient_info: Option<&'a AccountInfo<'b>>,
+        payment: Option<PaymentAccounts<'a, 'b>>,
+    },
+    /// the config authority mints for free, checked by the caller
+    Airdrop,
+}
+
+/// Optional accounts the randomized parts of a mint draw from.
+pub struct DrawAccounts<'a, 'b> {
+    pub lines_info: Option<&'a AccountInfo<'b>>,
+    pub slot_hashes_info: Option<&'a AccountInfo<'b>>,
+}
+
+fn process_public_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: MintNftArgs,
+    proof: Option<Vec<[u8; 32]>>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let authority_info = next_account_info(account_info_iter)?;
@@ -32,10 +154,678 @@ pub fn process_mint(
     let metadata_program_info = next_account_info(account_info_iter)?;
     let metadata_info = next_account_info(account_info_iter)?;
     let edition_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let treasury_info = next_account_info(account_info_iter)?;
+    let payment = PaymentAccounts::next_optional(program_id, account_info_iter)?;
+    let whitelist_info = optional_account(program_id, account_info_iter)?;
+    let counter_info = optional_account(program_id, account_info_iter)?;
+    let lines_info = optional_account(program_id, account_info_iter)?;
+    let slot_hashes_info = optional_account(program_id, account_info_iter)?;
+    let recipient_info = optional_account(program_id, account_info_iter)?.unwrap_or(signer_info);
+    let collection = CollectionAccounts::next_optional(program_id, account_info_iter)?;
+    let fee_recipient_info = optional_account(program_id, account_info_iter)?;
+    let session_info = optional_account(program_id, account_info_iter)?;
+
+    assert_signer(signer_info)?;
+    // a session key mints for its player, who is passed as the recipient
+    let minter_info = match session_info {
+        Some(session_info) => {
+            use_session(program_id, session_info, signer_info, recipient_info)?;
+            recipient_info
+        }
+        None => signer_info,
+    };
+    let shared = MintAccounts {
+        authority_info,
+        signer_info,
+        token_program_info,
+        ass_token_program_info,
+        rent_info,
+        system_info,
+        metadata_program_info,
+    };
+    let nft = NftAccounts {
+        mint_info,
+        ata_info,
+        metadata_info,
+        edition_info,
+        record_info,
+        recipient_info,
+    };
+    let authority_bump = assert_mint_authority(program_id, authority_info)?;
+    assert_mint_programs(&shared)?;
+    assert_expected_mint(mint_info, &args.expected_mint)?;
+    if let Some(recipient) = args.recipient {
+        assert_eq_pubkey(recipient_info, &recipient)?;
+    }
+    let return_mint = args.return_mint;
+    let kind = MintKind::Public {
+        minter_info,
+        proof,
+        whitelist_info,
+        counter_info,
+        treasury_info,
+        fee_recipient_info,
+        payment,
+    };
+    let draw = DrawAccounts {
+        lines_info,
+        slot_hashes_info,
+    };
+    let traits = mint_one(
+        program_id,
+        &shared,
+        &nft,
+        config_info,
+        &draw,
+        collection.as_ref(),
+        args,
+        kind,
+        authority_bump,
+    )?;
+    // the mint key when asked for, followed by the drawn trait indices
+    let mut return_data = vec![];
+    if return_mint {
+        return_data.extend_from_slice(mint_info.key.as_ref());
+    }
+    return_data.extend_from_slice(&traits);
+    if !return_data.is_empty() {
+        set_return_data(&return_data);
+    }
+    Ok(())
+}
+
+/// Mints one NFT of the config at `config_info` to `nft`, reserving its
+/// supply first. Returns the drawn trait indices.
+#[allow(clippy::too_many_arguments)]
+pub fn mint_one<'a, 'b>(
+    program_id: &Pubkey,
+    shared: &MintAccounts<'a, 'b>,
+    nft: &NftAccounts<'a, 'b>,
+    config_info: &'a AccountInfo<'b>,
+    draw: &DrawAccounts<'a, 'b>,
+    collection: Option<&CollectionAccounts<'a, 'b>>,
+    args: MintNftArgs,
+    kind: MintKind<'a, 'b>,
+    authority_bump: u8,
+) -> Result<Vec<u8>, ProgramError> {
+    let signer_info = shared.signer_info;
+    let record_bump = assert_record_derivation(program_id, nft)?;
+    assert_ata_derivation(nft, shared.token_program_info)?;
+    assert_metadata_derivation(nft)?;
+    let config = reserve_supply(program_id, config_info, 1)?;
+    let mut params = validate_mint_args(signer_info.key, args, &config.default_creators)?;
+    if let MintKind::Public {
+        minter_info,
+        proof,
+        whitelist_info,
+        counter_info,
+        ..
+    } = &kind
+    {
+        // a session key only stands in for its player, who becomes the
+        // creator, update authority and minter the key would otherwise have been
+        if minter_info.key != signer_info.key {
+            for creator in params.creators.iter_mut().flatten() {
+                if creator.address == *signer_info.key {
+                    creator.address = *minter_info.key;
+                    creator.verified = false;
+                }
+            }
+            params.update_authority.get_or_insert(*minter_info.key);
+            params.minter = *minter_info.key;
+        }
+        assert_can_mint(&config, minter_info)?;
+        if let Some(proof) = proof {
+            let leaf = hash(minter_info.key.as_ref()).to_bytes();
+            // an all-zero root means no allowlist phase is configured
+            if config.merkle_root == [0; 32]
+                || !verify_merkle_proof(leaf, proof, &config.merkle_root)
+            {
+                return Err(AppError::NotAllowlisted.into());
+            }
+        }
+        consume_whitelist(program_id, &config, config_info, minter_info, *whitelist_info, 1)?;
+        count_wallet_mints(
+            program_id,
+            &config,
+            config_info,
+            shared,
+            minter_info.key,
+            *counter_info,
+            1,
+        )?;
+    }
+    apply_uri_template(&config, &mut params, config.minted - 1)?;
+    assert_config_collection(&config, collection, &mut params)?;
+    let mint_info = nft.mint_info;
+    draw_config_line(
+        program_id,
+        &config,
+        config_info,
+        draw.lines_info,
+        draw.slot_hashes_info,
+        &mut params,
+        mint_info.key.as_ref(),
+    )?;
+    let traits = draw_traits(draw.slot_hashes_info, &mut params, mint_info.key.as_ref())?;
+    if let MintKind::Public {
+        treasury_info,
+        fee_recipient_info,
+        payment,
+        ..
+    } = kind
+    {
+        charge_mint_price(&config, shared, treasury_info, fee_recipient_info, payment, 1)?;
+    }
+
+    mint_nft(program_id, shared, nft, config_info.key, params, authority_bump, record_bump)?;
+    if let (Some(_), Some(collection)) = (config.collection_mint, collection) {
+        verify_collection_item(program_id, shared, nft.metadata_info, collection, authority_bump)?;
+    }
+    Ok(traits)
+}
+
+pub fn assert_mint_authority(program_id: &Pubkey, authority_info: &AccountInfo) -> Result<u8, ProgramError> {
+    assert_derivation(
+        program_id,
+        authority_info,
+        &[MINT_AUTHORITY_SEED, program_id.as_ref()],
+    )
+}
+
+/// Loads the collection config and takes `count` mints from its supply. The
+/// counter is written back here, before any CPI runs.
+pub fn reserve_supply(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+    count: u64,
+) -> Result<CollectionConfig, ProgramError> {
+    let mut config = CollectionConfig::from_account_info(program_id, config_info)?;
+    assert_derivation(
+        program_id,
+        config_info,
+        &[CONFIG_SEED, config.authority.as_ref()],
+    )?;
+    if config.sealed {
+        return Err(AppError::CollectionSealed.into());
+    }
+    let minted = checked_add(config.minted, count)?;
+    if minted > config.max_supply {
+        return Err(AppError::SupplyExhausted.into());
+    }
+    config.minted = minted;
+    config.save(config_info)?;
+    Ok(config)
+}
+
+/// Adds `count` to the minter's WalletCounter, creating it on the first mint,
+/// when the config has a `max_per_wallet`. The counter is derived from the
+/// config and the minter, so wallets can't share one; the signer pays for it.
+pub fn count_wallet_mints<'a>(
+    program_id: &Pubkey,
+    config: &CollectionConfig,
+    config_info: &AccountInfo<'a>,
+    shared: &MintAccounts<'_, 'a>,
+    minter: &Pubkey,
+    counter_info: Option<&AccountInfo<'a>>,
+    count: u64,
+) -> ProgramResult {
+    if config.max_per_wallet == 0 {
+        return Ok(());
+    }
+    let counter_info = counter_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let signer_info = shared.signer_info;
+    let bump = assert_derivation(
+        program_id,
+        counter_info,
+        &[WALLET_COUNTER_SEED, config_info.key.as_ref(), minter.as_ref()],
+    )?;
+    let minted = if counter_info.data_is_empty() {
+        create_or_allocate_account_raw(
+            *program_id,
+            counter_info,
+            shared.rent_info,
+            shared.system_info,
+            signer_info,
+            WalletCounter::LEN,
+            &[WALLET_COUNTER_SEED, config_info.key.as_ref(), minter.as_ref(), &[bump]],
+        )?;
+        0
+    } else {
+        WalletCounter::from_account_info(program_id, counter_info)?.count
+    };
+    let minted = checked_add(u64::from(minted), count)?;
+    if minted > u64::from(config.max_per_wallet) {
+        return Err(AppError::WalletLimitReached.into());
+    }
+    let counter = WalletCounter {
+        count: minted as u8,
+        bump,
+        version: STATE_VERSION,
+    };
+    counter.serialize(&mut *counter_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Replaces the uri of `params` with the config template, if there is one,
+/// resolved for the `index`-th mint of the collection.
+pub fn apply_uri_template(
+    config: &CollectionConfig,
+    params: &mut MintParams,
+    index: u64,
+) -> ProgramResult {
+    if let Some(template) = &config.uri_template {
+        let uri = resolve_uri(template, index, None);
+        if uri.len() > MAX_URI_LENGTH {
+            return Err(AppError::UriTooLong.into());
+        }
+        params.uri = uri;
+    }
+    Ok(())
+}
+
+/// Draws one index per entry of `trait_options` and writes them, joined with
+/// `-`, into the `{traits}` placeholder of the uri. `nonce` keeps draws of
+/// the same slot apart, callers pass the mint key.
+pub fn draw_traits(
+    slot_hashes_info: Option<&AccountInfo>,
+    params: &mut MintParams,
+    nonce: &[u8],
+) -> Result<Vec<u8>, ProgramError> {
+    if params.trait_options.is_empty() {
+        return Ok(vec![]);
+    }
+    let slot_hashes_info = slot_hashes_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let mut traits = Vec::with_capacity(params.trait_options.len());
+    for (position, options) in params.trait_options.iter().enumerate() {
+        let seed = [nonce, &[position as u8]].concat();
+        traits.push(get_random_range(slot_hashes_info, &seed, u64::from(*options))? as u8);
+    }
+    let joined = traits.iter().map(u8::to_string).collect::<Vec<_>>().join("-");
+    let uri = params.uri.replace(URI_TRAITS_PLACEHOLDER, &joined);
+    if uri.len() > MAX_URI_LENGTH {
+        return Err(AppError::UriTooLong.into());
+    }
+    params.uri = uri;
+    msg!("Traits {}", joined);
+    Ok(traits)
+}
+
+pub fn assert_can_mint(config: &CollectionConfig, signer_info: &AccountInfo) -> ProgramResult {
+    if config.is_paused {
+        return Err(AppError::ProgramPaused.into());
+    }
+    if !config.can_mint(signer_info.key) {
+        return Err(AppError::NotADelegate.into());
+    }
+    // the authority mints test pieces outside the window
+    if config.authority == *signer_info.key {
+        return Ok(());
+    }
+    let now = now_timestamp()?;
+    if now < config.mint_start {
+        return Err(AppError::MintNotLive.into());
+    }
+    if config.mint_end != 0 && now >= config.mint_end {
+        return Err(AppError::MintEnded.into());
+    }
+    Ok(())
+}
+
+/// Token accounts for paying in `CollectionConfig::payment_mint`, passed as
+/// optional slots so SOL-priced configs can leave them absent.
+pub struct PaymentAccounts<'a, 'b> {
+    pub mint_info: &'a AccountInfo<'b>,
+    pub source_info: &'a AccountInfo<'b>,
+    pub destination_info: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> PaymentAccounts<'a, 'b> {
+    /// The three payment slots are passed together or not at all; a partial
+    /// set is a client error rather than a silent fall back to lamports.
+    pub fn next_optional<I: Iterator<Item = &'a AccountInfo<'b>>>(
+        program_id: &Pubkey,
+        iter: &mut I,
+    ) -> Result<Option<Self>, ProgramError> {
+        let mint_info = optional_account(program_id, iter)?;
+        let source_info = optional_account(program_id, iter)?;
+        let destination_info = optional_account(program_id, iter)?;
+        Ok(match (mint_info, source_info, destination_info) {
+            (Some(mint_info), Some(source_info), Some(destination_info)) => Some(PaymentAccounts {
+                mint_info,
+                source_info,
+                destination_info,
+            }),
+            (None, None, None) => None,
+            _ => {
+                msg!("Payment mint, source and destination must be passed together");
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+        })
+    }
+}
+
+/// Charges the config price for `count` mints: `price_tokens` of the payment
+/// mint when one is set, `price_lamports` otherwise. The lamports are split
+/// by `protocol_fee_bps`: the protocol share goes to the fee recipient and
+/// the rest to the treasury rather than the authority's own wallet, the
+/// treasury being where the authority chose to receive mint proceeds.
+pub fn charge_mint_price<'a>(
+    config: &CollectionConfig,
+    shared: &MintAccounts<'_, 'a>,
+    treasury_info: &AccountInfo<'a>,
+    fee_recipient_info: Option<&AccountInfo<'a>>,
+    payment: Option<PaymentAccounts<'_, 'a>>,
+    count: u64,
+) -> ProgramResult {
+    assert_eq_pubkey(treasury_info, &config.treasury)?;
+    let signer_info = shared.signer_info;
+    if let Some(payment_mint) = config.payment_mint {
+        let payment = payment.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        assert_eq_pubkey(payment.mint_info, &payment_mint)?;
+        let token_program = TokenProgram::from_account_info(shared.token_program_info)?;
+        let source =
+            assert_token_account_owner(&token_program, payment.source_info, signer_info.key)?;
+        let destination =
+            assert_token_account_owner(&token_program, payment.destination_info, &config.treasury)?;
+        if source.mint != payment_mint || destination.mint != payment_mint {
+            return Err(AppError::InvalidPaymentMint.into());
+        }
+        let price = checked_mul(config.price_tokens, count)?;
+        if price == 0 {
+            return Ok(());
+        }
+        if source.amount < price {
+            return Err(AppError::InsufficientFunds.into());
+        }
+
+        msg!("Pay {} tokens", price);
+        return spl_token_transfer(
+            &token_program,
+            payment.source_info.clone(),
+            payment.mint_info.clone(),
+            payment.destination_info.clone(),
+            signer_info.clone(),
+            price,
+            &[],
+        );
+    }
+
+    let price = checked_mul(config.price_lamports, count)?;
+    if price == 0 {
+        return Ok(());
+    }
+    if signer_info.lamports() < price {
+        return Err(AppError::InsufficientFunds.into());
+    }
 
-    assert_signer(&signer_info)?;
-    let size = 82;
-    let rent = &Rent::from_account_info(&rent_info)?;
+    let (creator_share, protocol_share) = calculate_split(price, config.protocol_fee_bps)?;
+    if protocol_share > 0 {
+        let fee_recipient_info = fee_recipient_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        assert_eq_pubkey(fee_recipient_info, &config.fee_recipient)?;
+        msg!("Pay {} lamports protocol fee", protocol_share);
+        invoke(
+            &system_instruction::transfer(signer_info.key, fee_recipient_info.key, protocol_share),
+            &[signer_info.clone(), fee_recipient_info.clone(), shared.system_info.clone()],
+        )?;
+    }
+    msg!("Pay {} lamports", creator_share);
+    invoke(
+        &system_instruction::transfer(signer_info.key, treasury_info.key, creator_share),
+        &[signer_info.clone(), treasury_info.clone(), shared.system_info.clone()],
+    )
+}
+
+pub fn assert_record_derivation(program_id: &Pubkey, nft: &NftAccounts) -> Result<u8, ProgramError> {
+    assert_derivation(
+        program_id,
+        nft.record_info,
+        &[RECORD_SEED, nft.mint_info.key.as_ref()],
+    )
+}
+
+/// Checks the token account is the recipient's associated token account for
+/// the mint, so tokens can't be minted into an arbitrary account.
+pub fn assert_ata_derivation(nft: &NftAccounts, token_program_info: &AccountInfo) -> ProgramResult {
+    assert_derivation(
+        &spl_associated_token_account::id(),
+        nft.ata_info,
+        &[
+            nft.recipient_info.key.as_ref(),
+            token_program_info.key.as_ref(),
+            nft.mint_info.key.as_ref(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Checks the program accounts of a mint against the programs its CPIs are
+/// meant to reach.
+pub fn assert_mint_programs(shared: &MintAccounts) -> ProgramResult {
+    assert_nft_token_program(shared.token_program_info)?;
+    let programs = [
+        (
+            "associated token program",
+            shared.ass_token_program_info,
+            spl_associated_token_account::id(),
+            AppError::InvalidProgramId,
+        ),
+        ("system program", shared.system_info, system_program::id(), AppError::InvalidProgramId),
+        (
+            "metadata program",
+            shared.metadata_program_info,
+            mpl_token_metadata::id(),
+            AppError::InvalidMetadataProgram,
+        ),
+    ];
+    for (name, account_info, id, error) in programs.iter() {
+        if assert_eq_pubkey(account_info, id).is_err() {
+            msg!("Wrong {} account {}", name, account_info.key);
+            return Err((*error).into());
+        }
+    }
+    Ok(())
+}
+
+/// The token program NFTs are minted with. The pinned token metadata program
+/// only creates metadata for spl-token mints, so Token-2022 is refused up
+/// front rather than failing inside create_metadata.
+pub fn assert_nft_token_program<'a>(
+    token_program_info: &AccountInfo<'a>,
+) -> Result<TokenProgram<'a>, ProgramError> {
+    match TokenProgram::from_account_info(token_program_info) {
+        Ok(token_program @ TokenProgram::Legacy(_)) => Ok(token_program),
+        Ok(TokenProgram::Token2022(_)) => {
+            msg!("NFTs can't be minted with Token-2022");
+            Err(AppError::UnsupportedTokenProgram.into())
+        }
+        Err(_) => {
+            msg!("Wrong token program account {}", token_program_info.key);
+            Err(AppError::InvalidProgramId.into())
+        }
+    }
+}
+
+/// Checks the metadata and master edition accounts are the PDAs of the mint,
+/// before the metadata program gets to see them.
+pub fn assert_metadata_derivation(nft: &NftAccounts) -> ProgramResult {
+    let metadata_program_id = mpl_token_metadata::id();
+    let metadata_seeds = &[
+        PREFIX.as_bytes(),
+        metadata_program_id.as_ref(),
+        nft.mint_info.key.as_ref(),
+    ];
+    if assert_derivation(&metadata_program_id, nft.metadata_info, metadata_seeds).is_err() {
+        msg!("Wrong metadata account {}", nft.metadata_info.key);
+        return Err(AppError::InvalidDerivedKey.into());
+    }
+    let edition_seeds = &[
+        PREFIX.as_bytes(),
+        metadata_program_id.as_ref(),
+        nft.mint_info.key.as_ref(),
+        EDITION.as_bytes(),
+    ];
+    if assert_derivation(&metadata_program_id, nft.edition_info, edition_seeds).is_err() {
+        msg!("Wrong master edition account {}", nft.edition_info.key);
+        return Err(AppError::InvalidDerivedKey.into());
+    }
+    Ok(())
+}
+
+/// The new mint must be the keypair the client signed with, otherwise the
+/// create_account CPI fails with an opaque system program error.
+pub fn assert_expected_mint(mint_info: &AccountInfo, expected_mint: &Pubkey) -> ProgramResult {
+    if mint_info.key != expected_mint || !mint_info.is_signer {
+        return Err(AppError::MintKeyMismatch.into());
+    }
+    Ok(())
+}
+
+/// Checks the mint args and turns them into the params of one mint. Args
+/// without creators take `default_creators` from the config.
+pub fn validate_mint_args(
+    signer: &Pubkey,
+    args: MintNftArgs,
+    default_creators: &[CreatorArgs],
+) -> Result<MintParams, ProgramError> {
+    let MintNftArgs {
+        uri,
+        title,
+        symbol,
+        seller_fee_basis_points,
+        creators,
+        mutable,
+        max_supply,
+        decimals,
+        amount,
+        return_mint: _,
+        collection_mint,
+        strict,
+        sized_collection,
+        soulbound,
+        expected_mint: _,
+        recipient: _,
+        trait_options,
+        update_authority,
+    } = args;
+    if uri.is_empty() {
+        return Err(AppError::InvalidUri.into());
+    }
+    if uri.len() > MAX_URI_LENGTH {
+        return Err(AppError::UriTooLong.into());
+    }
+    let title = title.unwrap_or_else(|| String::from("Violent squirrel"));
+    if title.len() > MAX_NAME_LENGTH {
+        return Err(AppError::NameTooLong.into());
+    }
+    let symbol = symbol.unwrap_or_default();
+    if symbol.len() > MAX_SYMBOL_LENGTH {
+        return Err(AppError::SymbolTooLong.into());
+    }
+    if seller_fee_basis_points > 10_000 {
+        return Err(AppError::InvalidRoyalty.into());
+    }
+    let creators = creators.or_else(|| {
+        (!default_creators.is_empty()).then(|| default_creators.to_vec())
+    });
+    let creators = build_creators(signer, creators, seller_fee_basis_points)?;
+    if decimals > 9 {
+        return Err(AppError::InvalidDecimals.into());
+    }
+    if amount == 0 {
+        return Err(AppError::InvalidTokenAmount.into());
+    }
+    if trait_options.len() > MAX_TRAITS || trait_options.contains(&0) {
+        return Err(AppError::InconsistentMintArgs.into());
+    }
+    if collection_mint == Some(Pubkey::default()) {
+        return Err(AppError::InvalidCollectionMint.into());
+    }
+    // only the collection authority can verify, see SetCollection
+    let collection = collection_mint.map(|key| Collection { key, verified: false });
+    // the metadata program starts every sized collection at 0 and counts itself
+    let collection_details = sized_collection.then_some(CollectionDetails::V1 { size: 0 });
+
+    let params = MintParams {
+        title,
+        symbol,
+        uri,
+        seller_fee_basis_points,
+        creators,
+        mutable,
+        max_supply,
+        decimals,
+        amount,
+        collection,
+        collection_details,
+        soulbound,
+        trait_options,
+        update_authority,
+        minter: *signer,
+    };
+    if strict {
+        validate_strict(&params)?;
+    }
+    Ok(params)
+}
+
+/// Cross-field checks for strict mode, rejecting combinations where part of
+/// the request would have no effect.
+fn validate_strict(params: &MintParams) -> ProgramResult {
+    // Some(0) is the default every client sends, anything else asks for prints
+    if params.max_supply != Some(0) && !params.is_edition() {
+        msg!(
+            "Strict: max_supply {:?} needs a master edition, decimals {} amount {} has none",
+            params.max_supply,
+            params.decimals,
+            params.amount
+        );
+        return Err(AppError::InconsistentMintArgs.into());
+    }
+    if params.collection_details.is_some() && !params.is_edition() {
+        msg!(
+            "Strict: sized collection needs a master edition, decimals {} amount {} has none",
+            params.decimals,
+            params.amount
+        );
+        return Err(AppError::InconsistentMintArgs.into());
+    }
+    if params.decimals > 0 && params.collection.is_some() {
+        msg!("Strict: collection set on a fungible mint with decimals {}", params.decimals);
+        return Err(AppError::InconsistentMintArgs.into());
+    }
+    Ok(())
+}
+
+/// Creates `mint_info` under the mint authority PDA, the recipient's
+/// associated token account, and mints `amount` into it.
+#[allow(clippy::too_many_arguments)]
+pub fn mint_tokens<'a, 'b>(
+    program_id: &Pubkey,
+    shared: &MintAccounts<'a, 'b>,
+    mint_info: &'a AccountInfo<'b>,
+    ata_info: &'a AccountInfo<'b>,
+    recipient_info: &'a AccountInfo<'b>,
+    decimals: u8,
+    amount: u64,
+    authority_bump: u8,
+) -> ProgramResult {
+    let MintAccounts {
+        authority_info,
+        signer_info,
+        token_program_info,
+        ass_token_program_info,
+        rent_info,
+        system_info,
+        ..
+    } = *shared;
+    let authority_seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, program_id.as_ref(), &[authority_bump]];
+    let token_program = assert_nft_token_program(token_program_info)?;
+
+    let size = token_program.mint_len();
+    let rent = &Rent::from_account_info(rent_info)?;
     let required_lamports = rent.minimum_balance(size);
 
     msg!("Create Account");
@@ -52,113 +842,909 @@ pub fn process_mint(
 
     msg!("Initialize Mint");
     invoke(
-        &initialize_mint(
-            token_program_info.key,
+        &spl_token::instruction::initialize_mint(
+            token_program.id(),
             mint_info.key,
             authority_info.key,
             Some(authority_info.key),
-            0,
+            decimals,
         )?,
-        &[authority_info.clone(), mint_info.clone(), rent_info.clone(), token_program_info.clone(), ],
+        &[mint_info.clone(), rent_info.clone(), token_program_info.clone()],
     )?;
+    assert_initialized::<Mint>(mint_info)?;
+    assert_rent_exempt(rent, mint_info)?;
 
     msg!("Create Associated Token Account");
     invoke(
-        &create_associated_token_account(
-            signer_info.key,
+        &token_program.create_associated_token_account(
             signer_info.key,
+            recipient_info.key,
             mint_info.key,
         ),
         &[
             signer_info.clone(),
             ata_info.clone(),
+            recipient_info.clone(),
             ass_token_program_info.clone(),
             mint_info.clone(),
             token_program_info.clone(),
             system_info.clone()
         ],
     )?;
+    if token_program.account_mint_and_owner(ata_info)? != (*mint_info.key, *recipient_info.key) {
+        return Err(AppError::InvalidTokenAccount.into());
+    }
 
     msg!("Mint To");
-    invoke(
-        &mint_to(
-            token_program_info.key,
+    invoke_signed(
+        &spl_token::instruction::mint_to(
+            token_program.id(),
             mint_info.key,
             ata_info.key,
-            signer_info.key,
-            &[signer_info.key],
-            1,
+            authority_info.key,
+            &[],
+            amount,
         )?,
         &[
-            signer_info.clone(),
+            authority_info.clone(),
             ata_info.clone(),
             mint_info.clone(),
             token_program_info.clone(),
-            system_info.clone()
         ],
+        &[authority_seeds],
+    )?;
+    Ok(())
+}
+
+/// Creates the mint, the signer's token account, metadata, master edition and
+/// mint record of one NFT, recording `config` as the config it belongs to.
+pub fn mint_nft<'a, 'b>(
+    program_id: &Pubkey,
+    shared: &MintAccounts<'a, 'b>,
+    nft: &NftAccounts<'a, 'b>,
+    config: &Pubkey,
+    params: MintParams,
+    authority_bump: u8,
+    record_bump: u8,
+) -> ProgramResult {
+    let MintAccounts {
+        authority_info,
+        signer_info,
+        token_program_info,
+        rent_info,
+        system_info,
+        metadata_program_info,
+        ..
+    } = *shared;
+    let NftAccounts {
+        mint_info,
+        ata_info,
+        metadata_info,
+        edition_info,
+        record_info,
+        recipient_info,
+    } = *nft;
+    let authority_seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, program_id.as_ref(), &[authority_bump]];
+
+    mint_tokens(
+        program_id,
+        shared,
+        mint_info,
+        ata_info,
+        recipient_info,
+        params.decimals,
+        params.amount,
+        authority_bump,
     )?;
 
+    if params.soulbound {
+        freeze_token_account(
+            program_id,
+            authority_info,
+            mint_info,
+            ata_info,
+            &assert_nft_token_program(token_program_info)?,
+            authority_bump,
+            true,
+        )?;
+    }
+
+    let is_edition = params.is_edition();
     msg!("Create Metadata Account");
-    let creator = vec![
-        mpl_token_metadata::state::Creator {
-            address: *signer_info.key,
-            verified: false,
-            share: 100,
-        },
-    ];
-    let title = if title.is_none() {String::from("Violent squirrel")}else{title.unwrap()};
-    let symbol = String::from("VS");
-    invoke(
-        &create_metadata_accounts_v2(
+    invoke_signed(
+        &create_metadata_accounts_v3(
             *metadata_program_info.key,
             *metadata_info.key,
             *mint_info.key,
+            *authority_info.key,
             *signer_info.key,
             *signer_info.key,
-            *signer_info.key,
-            title,
-            symbol,
-            uri,
-            Some(creator),
-            1,
+            params.title,
+            params.symbol,
+            params.uri,
+            params.creators,
+            params.seller_fee_basis_points,
             true,
-            false,
-            None,
+            params.mutable,
+            params.collection,
             None,
+            params.collection_details,
         ),
         &[
             metadata_info.clone(),
             mint_info.clone(),
+            authority_info.clone(),
             signer_info.clone(),
             metadata_program_info.clone(),
             token_program_info.clone(),
             system_info.clone(),
             rent_info.clone(),
         ],
+        &[authority_seeds],
     )?;
 
-    msg!("Create Master Edition");
-    invoke(
-        &create_master_edition_v3(
-            *metadata_program_info.key,
-            *edition_info.key,
-            *mint_info.key,
-            *signer_info.key,
-            *signer_info.key,
-            *metadata_info.key,
-            *signer_info.key,
-            Some(1),
-        ),
-        &[
-            edition_info.clone(),
-            mint_info.clone(),
-            signer_info.clone(),
-            metadata_info.clone(),
-            metadata_program_info.clone(),
-            token_program_info.clone(),
-            system_info.clone(),
-            rent_info.clone(),
-        ],
+    if is_edition {
+        msg!("Create Master Edition");
+        invoke_signed(
+            &create_master_edition_v3(
+                *metadata_program_info.key,
+                *edition_info.key,
+                *mint_info.key,
+                *signer_info.key,
+                *authority_info.key,
+                *metadata_info.key,
+                *signer_info.key,
+                params.max_supply,
+            ),
+            &[
+                edition_info.clone(),
+                mint_info.clone(),
+                signer_info.clone(),
+                authority_info.clone(),
+                metadata_info.clone(),
+                metadata_program_info.clone(),
+                token_program_info.clone(),
+                system_info.clone(),
+                rent_info.clone(),
+            ],
+            &[authority_seeds],
+        )?;
+    }
+
+    // the master edition needs the signer as update authority, so another
+    // authority only takes over once the edition exists
+    if let Some(update_authority) = params.update_authority.filter(|key| key != signer_info.key) {
+        msg!("Transfer Update Authority");
+        invoke(
+            &update_metadata_accounts_v2(
+                *metadata_program_info.key,
+                *metadata_info.key,
+                *signer_info.key,
+                Some(update_authority),
+                None,
+                None,
+                None,
+            ),
+            &[
+                metadata_info.clone(),
+                signer_info.clone(),
+                metadata_program_info.clone(),
+            ],
+        )?;
+    }
+
+    msg!("Create Mint Record");
+    create_or_allocate_account_raw(
+        *program_id,
+        record_info,
+        rent_info,
+        system_info,
+        signer_info,
+        MintRecord::LEN,
+        &[RECORD_SEED, mint_info.key.as_ref(), &[record_bump]],
     )?;
+    let clock = Clock::get()?;
+    let record = MintRecord {
+        mint: *mint_info.key,
+        minter: params.minter,
+        config: *config,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+        bump: record_bump,
+        version: STATE_VERSION,
+    };
+    record.serialize(&mut *record_info.data.borrow_mut())?;
     Ok(())
-}
\ No newline at end of file
+}
+
+fn build_creators(
+    signer: &Pubkey,
+    creators: Option<Vec<CreatorArgs>>,
+    seller_fee_basis_points: u16,
+) -> Result<Option<Vec<Creator>>, ProgramError> {
+    let creators = match creators {
+        Some(creators) => creators,
+        None => vec![CreatorArgs {
+            address: *signer,
+            share: 100,
+            provenance_only: false,
+        }],
+    };
+    if creators.is_empty() {
+        // a royalty needs at least one creator to be paid to
+        if seller_fee_basis_points > 0 {
+            return Err(AppError::RoyaltyWithoutCreators.into());
+        }
+        return Ok(None);
+    }
+    validate_creators(&creators)?;
+    Ok(Some(creators
+        .into_iter()
+        // only the signer, who is the update authority, can be verified at creation
+        .map(|creator| Creator {
+            address: creator.address,
+            verified: creator.address == *signer,
+            share: creator.share,
+        })
+        .collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instruction::GameInstruction,
+        processor::process_instruction,
+        state::{WalletCounter, WhitelistEntry, WALLET_COUNTER_SEED, WHITELIST_SEED},
+        test_utils::*,
+    };
+    use mpl_token_metadata::instruction::MetadataInstruction;
+    use solana_program::program_pack::Pack;
+    use spl_token::{instruction::TokenInstruction, state::Account};
+
+    #[test]
+    fn nft_token_program_is_spl_token() {
+        let mut token_program = TestAccount::program(spl_token::id());
+        let token_program = assert_nft_token_program(&token_program.info()).unwrap();
+        assert!(matches!(token_program, TokenProgram::Legacy(_)));
+    }
+
+    #[test]
+    fn nft_token_program_refuses_token_2022() {
+        let mut token_program = TestAccount::program(spl_token_2022::id());
+        assert_eq!(
+            assert_nft_token_program(&token_program.info()).err(),
+            Some(AppError::UnsupportedTokenProgram.into())
+        );
+    }
+
+    #[test]
+    fn nft_token_program_refuses_other_programs() {
+        let mut token_program = TestAccount::program(Pubkey::new_unique());
+        assert_eq!(
+            assert_nft_token_program(&token_program.info()).err(),
+            Some(AppError::InvalidProgramId.into())
+        );
+    }
+
+    #[test]
+    fn payment_accounts_are_all_or_nothing() {
+        let mut full = vec![
+            TestAccount::wallet(Pubkey::new_unique()),
+            TestAccount::wallet(Pubkey::new_unique()),
+            TestAccount::wallet(Pubkey::new_unique()),
+        ];
+        let full = infos(&mut full);
+        assert!(PaymentAccounts::next_optional(&crate::id(), &mut full.iter()).unwrap().is_some());
+
+        let mut none = vec![absent(), absent(), absent()];
+        let none = infos(&mut none);
+        assert!(PaymentAccounts::next_optional(&crate::id(), &mut none.iter()).unwrap().is_none());
+    }
+
+    #[test]
+    fn payment_accounts_reject_a_partial_set() {
+        for missing in 0..3 {
+            let mut accounts = vec![
+                TestAccount::wallet(Pubkey::new_unique()),
+                TestAccount::wallet(Pubkey::new_unique()),
+                TestAccount::wallet(Pubkey::new_unique()),
+            ];
+            accounts[missing] = absent();
+            let accounts = infos(&mut accounts);
+            assert_eq!(
+                PaymentAccounts::next_optional(&crate::id(), &mut accounts.iter()).err(),
+                Some(ProgramError::NotEnoughAccountKeys)
+            );
+        }
+    }
+
+    #[test]
+    fn mint_args_without_creators_take_the_config_defaults() {
+        let signer = Pubkey::new_unique();
+        let artist = Pubkey::new_unique();
+        let defaults = [CreatorArgs { address: artist, share: 100, provenance_only: false }];
+        let params =
+            validate_mint_args(&signer, test_mint_args(Pubkey::new_unique()), &defaults).unwrap();
+        let creators = params.creators.unwrap();
+        assert_eq!(creators.len(), 1);
+        assert_eq!((creators[0].address, creators[0].share), (artist, 100));
+        assert!(!creators[0].verified);
+    }
+
+    #[test]
+    fn mint_args_without_creators_or_defaults_pay_the_signer() {
+        let signer = Pubkey::new_unique();
+        let params =
+            validate_mint_args(&signer, test_mint_args(Pubkey::new_unique()), &[]).unwrap();
+        let creators = params.creators.unwrap();
+        assert_eq!(creators.len(), 1);
+        assert_eq!((creators[0].address, creators[0].share), (signer, 100));
+        assert!(creators[0].verified);
+    }
+
+    #[test]
+    fn mint_args_creators_override_the_config_defaults() {
+        let signer = Pubkey::new_unique();
+        let artist =
+            CreatorArgs { address: Pubkey::new_unique(), share: 100, provenance_only: false };
+        let mut args = test_mint_args(Pubkey::new_unique());
+        args.creators = Some(vec![artist.clone(), artist]);
+        assert_eq!(
+            validate_mint_args(&signer, args, &[]).err(),
+            Some(AppError::DuplicateCreator.into())
+        );
+    }
+
+    /// Mint accounts and args of a plain NFT by a fresh wallet under a free
+    /// config of another authority.
+    fn public_mint() -> (Vec<TestAccount>, MintNftArgs) {
+        let accounts = mint_accounts(&test_config(Pubkey::new_unique()), Pubkey::new_unique());
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        (accounts, args)
+    }
+
+    #[test]
+    fn mint_instruction_passes_the_callers_strings_to_the_metadata_cpi() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.title = Some(String::from("Squirrel #7"));
+        args.uri = String::from("https://example.com/7.json");
+        let input = GameInstruction::Mint(args).try_to_vec().unwrap();
+        process_instruction(&crate::id(), &infos(&mut accounts), &input).unwrap();
+
+        let created = created_metadata();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].name, "Squirrel #7");
+        assert_eq!(created[0].uri, "https://example.com/7.json");
+    }
+
+    #[test]
+    fn mint_rejects_an_empty_uri() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.uri = String::new();
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::InvalidUri.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn mint_rejects_a_uri_over_the_metadata_limit() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.uri = "a".repeat(MAX_URI_LENGTH + 1);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::UriTooLong.into())
+        );
+
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.uri = "a".repeat(MAX_URI_LENGTH);
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+    }
+
+    /// Fills the payment slots with token accounts of `payment_mint`, the
+    /// signer's holding `balance`.
+    fn add_payment(accounts: &mut [TestAccount], payment_mint: Pubkey, balance: u64) {
+        let signer = accounts[mint_slot::SIGNER].key;
+        let treasury = accounts[mint_slot::TREASURY].key;
+        accounts[mint_slot::PAYMENT_MINT] =
+            TestAccount::mint(payment_mint, &spl_token::id(), &Pubkey::new_unique(), 6);
+        accounts[mint_slot::PAYMENT_SOURCE] = TestAccount::token_account(
+            ata(&signer, &payment_mint),
+            &spl_token::id(),
+            &payment_mint,
+            &signer,
+            balance,
+        );
+        accounts[mint_slot::PAYMENT_DESTINATION] = TestAccount::token_account(
+            ata(&treasury, &payment_mint),
+            &spl_token::id(),
+            &payment_mint,
+            &treasury,
+            0,
+        );
+    }
+
+    /// Fills the whitelist slot with an entry of the signer.
+    fn add_whitelist_entry(accounts: &mut [TestAccount], remaining_mints: u64) {
+        let (config, wallet) = (accounts[mint_slot::CONFIG].key, accounts[mint_slot::SIGNER].key);
+        let key = pda(&[WHITELIST_SEED, config.as_ref(), wallet.as_ref()]);
+        let entry =
+            WhitelistEntry { config, wallet, remaining_mints, bump: 0, version: STATE_VERSION };
+        accounts[mint_slot::WHITELIST] =
+            TestAccount::state(key, crate::id(), &entry, WhitelistEntry::LEN);
+    }
+
+    #[test]
+    fn mint_reads_each_combination_of_optional_groups() {
+        for combination in 0..8 {
+            let (payment, whitelist, slot_hashes) =
+                (combination & 1 != 0, combination & 2 != 0, combination & 4 != 0);
+            setup();
+            let mut config = test_config(Pubkey::new_unique());
+            let payment_mint = Pubkey::new_unique();
+            if payment {
+                config.payment_mint = Some(payment_mint);
+                config.price_tokens = 10;
+            }
+            config.whitelist_enabled = whitelist;
+            let mut accounts = mint_accounts(&config, Pubkey::new_unique());
+            let mut args = test_mint_args(accounts[mint_slot::MINT].key);
+            if payment {
+                add_payment(&mut accounts, payment_mint, 10);
+            }
+            if whitelist {
+                add_whitelist_entry(&mut accounts, 1);
+            }
+            if slot_hashes {
+                accounts[mint_slot::SLOT_HASHES] = TestAccount::slot_hashes([7; 32]);
+                args.trait_options = vec![4];
+                args.uri = String::from("https://example.com/{traits}.json");
+            }
+
+            process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+            let transfers = invoked_program(&spl_token::id())
+                .iter()
+                .filter(|call| {
+                    call.instruction.accounts[0].pubkey == accounts[mint_slot::PAYMENT_SOURCE].key
+                })
+                .count();
+            assert_eq!(transfers, usize::from(payment), "combination {}", combination);
+            if whitelist {
+                let entry: WhitelistEntry =
+                    try_from_slice_unchecked(&accounts[mint_slot::WHITELIST].data).unwrap();
+                assert_eq!(entry.remaining_mints, 0);
+            }
+            // the drawn trait index is the only return data
+            assert_eq!(return_data().is_some(), slot_hashes, "combination {}", combination);
+            assert!(!created_metadata()[0].uri.contains("{traits}"));
+        }
+    }
+
+    #[test]
+    fn mint_reports_a_missing_optional_group() {
+        setup();
+        let mut config = test_config(Pubkey::new_unique());
+        config.payment_mint = Some(Pubkey::new_unique());
+        let mut accounts = mint_accounts(&config, Pubkey::new_unique());
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.trait_options = vec![4];
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn mint_stores_the_submitted_symbol() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.symbol = Some(String::from("SQRL"));
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+        assert_eq!(created_metadata()[0].symbol, "SQRL");
+
+        setup();
+        let (mut accounts, args) = public_mint();
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+        assert_eq!(created_metadata()[0].symbol, "");
+    }
+
+    #[test]
+    fn mint_rejects_a_symbol_over_the_metadata_limit() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.symbol = Some("S".repeat(MAX_SYMBOL_LENGTH + 1));
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::SymbolTooLong.into())
+        );
+    }
+
+    #[test]
+    fn mint_record_carries_the_slot_and_timestamp() {
+        setup();
+        set_clock(4_242, 1_700_000_000);
+        let (mut accounts, args) = public_mint();
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+
+        let record = load_record(&accounts[mint_slot::RECORD]);
+        assert_eq!(record.mint, accounts[mint_slot::MINT].key);
+        assert_eq!(record.minter, accounts[mint_slot::SIGNER].key);
+        assert_eq!(record.config, accounts[mint_slot::CONFIG].key);
+        assert_eq!((record.slot, record.unix_timestamp), (4_242, 1_700_000_000));
+    }
+
+    #[test]
+    fn mint_passes_the_edition_cap_to_the_master_edition_cpi() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.max_supply = Some(1);
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+
+        let caps: Vec<_> = metadata_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                MetadataInstruction::CreateMasterEditionV3(args) => Some(args.max_supply),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(caps, vec![Some(1)]);
+    }
+
+    #[test]
+    fn mint_rejects_royalties_without_creators() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.seller_fee_basis_points = 500;
+        args.creators = Some(vec![]);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::RoyaltyWithoutCreators.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn mint_fills_the_supply_exactly_and_then_stops() {
+        setup();
+        let mut config = test_config(Pubkey::new_unique());
+        config.max_supply = 2;
+        config.minted = 1;
+        let mut accounts = mint_accounts(&config, Pubkey::new_unique());
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+        assert_eq!(load_config(&accounts[mint_slot::CONFIG]).minted, 2);
+
+        setup();
+        let mint = Pubkey::new_unique();
+        set_mint(&mut accounts, mint);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), test_mint_args(mint)),
+            Err(AppError::SupplyExhausted.into())
+        );
+        assert_eq!(load_config(&accounts[mint_slot::CONFIG]).minted, 2);
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn mint_rejects_a_config_stored_under_another_authority() {
+        setup();
+        let (mut accounts, args) = public_mint();
+        let mut config = load_config(&accounts[mint_slot::CONFIG]);
+        config.authority = Pubkey::new_unique();
+        let key = accounts[mint_slot::CONFIG].key;
+        accounts[mint_slot::CONFIG] =
+            TestAccount::state(key, crate::id(), &config, CollectionConfig::LEN);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::InvalidDerivedKey.into())
+        );
+    }
+
+    #[test]
+    fn mint_by_a_delegate_succeeds() {
+        setup();
+        let delegate = Pubkey::new_unique();
+        let mut config = test_config(Pubkey::new_unique());
+        config.delegates = vec![Pubkey::new_unique(), delegate];
+        let mut accounts = mint_accounts(&config, delegate);
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+        assert_eq!(load_config(&accounts[mint_slot::CONFIG]).minted, 1);
+    }
+
+    #[test]
+    fn mint_by_a_non_delegate_is_rejected() {
+        setup();
+        let mut config = test_config(Pubkey::new_unique());
+        config.delegates = vec![Pubkey::new_unique()];
+        let mut accounts = mint_accounts(&config, Pubkey::new_unique());
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::NotADelegate.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    /// Mint accounts of a config priced at 10 tokens of a fresh payment mint,
+    /// with the signer holding `balance` of it.
+    fn token_priced_mint(balance: u64) -> (Vec<TestAccount>, MintNftArgs) {
+        let payment_mint = Pubkey::new_unique();
+        let mut config = test_config(Pubkey::new_unique());
+        config.payment_mint = Some(payment_mint);
+        config.price_tokens = 10;
+        let mut accounts = mint_accounts(&config, Pubkey::new_unique());
+        add_payment(&mut accounts, payment_mint, balance);
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        (accounts, args)
+    }
+
+    #[test]
+    fn mint_rejects_a_payment_source_of_another_mint() {
+        setup();
+        let (mut accounts, args) = token_priced_mint(10);
+        let signer = accounts[mint_slot::SIGNER].key;
+        let other_mint = Pubkey::new_unique();
+        accounts[mint_slot::PAYMENT_SOURCE] = TestAccount::token_account(
+            ata(&signer, &other_mint),
+            &spl_token::id(),
+            &other_mint,
+            &signer,
+            10,
+        );
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::InvalidPaymentMint.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn mint_rejects_a_payment_destination_of_another_mint() {
+        setup();
+        let (mut accounts, args) = token_priced_mint(10);
+        let treasury = accounts[mint_slot::TREASURY].key;
+        let other_mint = Pubkey::new_unique();
+        accounts[mint_slot::PAYMENT_DESTINATION] = TestAccount::token_account(
+            ata(&treasury, &other_mint),
+            &spl_token::id(),
+            &other_mint,
+            &treasury,
+            0,
+        );
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::InvalidPaymentMint.into())
+        );
+    }
+
+    #[test]
+    fn mint_rejects_a_token_balance_below_the_price() {
+        setup();
+        let (mut accounts, args) = token_priced_mint(9);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::InsufficientFunds.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn strict_mint_args_reject_contradictions() {
+        let signer = Pubkey::new_unique();
+        let strict = MintNftArgs { strict: true, ..test_mint_args(Pubkey::new_unique()) };
+        let contradictions = [
+            MintNftArgs { max_supply: Some(5), amount: 2, ..strict.clone() },
+            MintNftArgs { sized_collection: true, decimals: 2, ..strict.clone() },
+            MintNftArgs {
+                collection_mint: Some(Pubkey::new_unique()),
+                decimals: 2,
+                max_supply: Some(0),
+                ..strict.clone()
+            },
+        ];
+        for args in contradictions {
+            let lenient = MintNftArgs { strict: false, ..args.clone() };
+            assert!(validate_mint_args(&signer, lenient, &[]).is_ok());
+            assert_eq!(
+                validate_mint_args(&signer, args, &[]).err(),
+                Some(AppError::InconsistentMintArgs.into())
+            );
+        }
+        assert!(validate_mint_args(&signer, strict, &[]).is_ok());
+    }
+
+    #[test]
+    fn mint_stops_at_the_wallet_limit() {
+        setup();
+        let mut config = test_config(Pubkey::new_unique());
+        config.max_per_wallet = 2;
+        let mut accounts = mint_accounts(&config, Pubkey::new_unique());
+        let config_key = accounts[mint_slot::CONFIG].key;
+        let signer = accounts[mint_slot::SIGNER].key;
+        accounts[mint_slot::COUNTER] = TestAccount::state(
+            pda(&[WALLET_COUNTER_SEED, config_key.as_ref(), signer.as_ref()]),
+            crate::id(),
+            &WalletCounter { count: 0, bump: 0, version: STATE_VERSION },
+            WalletCounter::LEN,
+        );
+        for _ in 0..2 {
+            setup();
+            let mint = Pubkey::new_unique();
+            set_mint(&mut accounts, mint);
+            process_mint(&crate::id(), &infos(&mut accounts), test_mint_args(mint)).unwrap();
+        }
+        let counter: WalletCounter =
+            try_from_slice_unchecked(&accounts[mint_slot::COUNTER].data).unwrap();
+        assert_eq!(counter.count, 2);
+
+        setup();
+        let mint = Pubkey::new_unique();
+        set_mint(&mut accounts, mint);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), test_mint_args(mint)),
+            Err(AppError::WalletLimitReached.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    /// Mint accounts of a config open from 1_000 until 2_000.
+    fn windowed_mint() -> (Vec<TestAccount>, MintNftArgs) {
+        let mut config = test_config(Pubkey::new_unique());
+        config.mint_start = 1_000;
+        config.mint_end = 2_000;
+        let accounts = mint_accounts(&config, Pubkey::new_unique());
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        (accounts, args)
+    }
+
+    #[test]
+    fn mint_before_the_window_is_rejected() {
+        setup();
+        set_clock(1, 999);
+        let (mut accounts, args) = windowed_mint();
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::MintNotLive.into())
+        );
+    }
+
+    #[test]
+    fn mint_after_the_window_is_rejected() {
+        setup();
+        set_clock(1, 2_000);
+        let (mut accounts, args) = windowed_mint();
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::MintEnded.into())
+        );
+    }
+
+    #[test]
+    fn mint_inside_the_window_succeeds() {
+        setup();
+        set_clock(1, 1_000);
+        let (mut accounts, args) = windowed_mint();
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+    }
+
+    #[test]
+    fn mint_rejects_an_unexpected_mint_key() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        args.expected_mint = Pubkey::new_unique();
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::MintKeyMismatch.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn mint_rejects_a_mint_that_did_not_sign() {
+        setup();
+        let (mut accounts, args) = public_mint();
+        accounts[mint_slot::MINT].is_signer = false;
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::MintKeyMismatch.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn mint_to_a_recipient_fills_the_recipients_token_account() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        let recipient = Pubkey::new_unique();
+        set_mint_recipient(&mut accounts, recipient);
+        args.recipient = Some(recipient);
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+
+        let (mint, signer) = (accounts[mint_slot::MINT].key, accounts[mint_slot::SIGNER].key);
+        let recipient_ata = ata(&recipient, &mint);
+        let create = invoked_program(&spl_associated_token_account::id());
+        let create = &create[0].instruction.accounts;
+        assert_eq!(
+            (create[0].pubkey, create[1].pubkey, create[2].pubkey),
+            (signer, recipient_ata, recipient)
+        );
+        let mint_to = invoked_program(&spl_token::id())
+            .into_iter()
+            .find_map(|call| match TokenInstruction::unpack(&call.instruction.data) {
+                Ok(TokenInstruction::MintTo { amount }) => {
+                    Some((call.instruction.accounts[1].pubkey, amount))
+                }
+                _ => None,
+            });
+        assert_eq!(mint_to, Some((recipient_ata, 1)));
+        let balance = Account::unpack(&accounts[mint_slot::ATA].data).unwrap();
+        assert_eq!((balance.owner, balance.amount), (recipient, 1));
+    }
+
+    #[test]
+    fn mint_to_a_recipient_that_never_signs() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        let recipient = Pubkey::new_unique();
+        set_mint_recipient(&mut accounts, recipient);
+        args.recipient = Some(recipient);
+        assert!(!accounts[mint_slot::RECIPIENT].is_signer);
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+
+        let record = load_record(&accounts[mint_slot::RECORD]);
+        assert_eq!(record.minter, accounts[mint_slot::SIGNER].key);
+    }
+
+    #[test]
+    fn mint_rejects_a_token_account_of_another_wallet() {
+        setup();
+        let (mut accounts, mut args) = public_mint();
+        let recipient = Pubkey::new_unique();
+        set_mint_recipient(&mut accounts, recipient);
+        args.recipient = Some(recipient);
+        let mint = accounts[mint_slot::MINT].key;
+        let signer = accounts[mint_slot::SIGNER].key;
+        accounts[mint_slot::ATA] =
+            TestAccount::token_account(ata(&signer, &mint), &spl_token::id(), &mint, &signer, 1);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::InvalidDerivedKey.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn mint_refuses_a_wallet_counter_from_a_newer_version() {
+        setup();
+        let mut config = test_config(Pubkey::new_unique());
+        config.max_per_wallet = 2;
+        let mut accounts = mint_accounts(&config, Pubkey::new_unique());
+        let config_key = accounts[mint_slot::CONFIG].key;
+        let signer = accounts[mint_slot::SIGNER].key;
+        accounts[mint_slot::COUNTER] = TestAccount::state(
+            pda(&[WALLET_COUNTER_SEED, config_key.as_ref(), signer.as_ref()]),
+            crate::id(),
+            &WalletCounter { count: 0, bump: 0, version: STATE_VERSION + 1 },
+            WalletCounter::LEN,
+        );
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::StateFromNewerVersion.into())
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use mpl_token_metadata::{
+    instruction::mint_new_edition_from_master_edition_via_token,
+    state::{MasterEditionV2, EDITION, EDITION_MARKER_BIT_SIZE, PREFIX},
+};
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    processor::{
+        config::assert_not_sealed,
+        mint::{assert_mint_authority, assert_nft_token_program, mint_tokens, MintAccounts},
+    },
+    state::MINT_AUTHORITY_SEED,
+    utils::*,
+};
+
+pub fn process_print_edition(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    edition_number: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let signer_info = next_account_info(account_info_iter)?;
+    let master_mint_info = next_account_info(account_info_iter)?;
+    let master_metadata_info = next_account_info(account_info_iter)?;
+    let master_edition_info = next_account_info(account_info_iter)?;
+    let master_token_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let ata_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let edition_info = next_account_info(account_info_iter)?;
+    let edition_mark_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let ass_token_program_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_signer(signer_info)?;
+    let authority_bump = assert_mint_authority(program_id, authority_info)?;
+    assert_metadata_program(metadata_program_info)?;
+    assert_owned_by(master_metadata_info, &mpl_token_metadata::id())?;
+    assert_owned_by(master_edition_info, &mpl_token_metadata::id())?;
+    let token_program = assert_nft_token_program(token_program_info)?;
+    let master_token =
+        assert_token_account_owner(&token_program, master_token_info, signer_info.key)?;
+    if master_token.mint != *master_mint_info.key || master_token.amount != 1 {
+        return Err(AppError::NotTokenOwner.into());
+    }
+    assert_not_sealed(program_id, master_mint_info.key, record_info, config_info)?;
+
+    let metadata_program_id = mpl_token_metadata::id();
+    let mark = (edition_number / EDITION_MARKER_BIT_SIZE).to_string();
+    assert_derivation(
+        &metadata_program_id,
+        edition_mark_info,
+        &[
+            PREFIX.as_bytes(),
+            metadata_program_id.as_ref(),
+            master_mint_info.key.as_ref(),
+            EDITION.as_bytes(),
+            mark.as_bytes(),
+        ],
+    )?;
+
+    let master_edition = MasterEditionV2::from_account_info(master_edition_info)?;
+    if let Some(max_supply) = master_edition.max_supply {
+        if edition_number > max_supply {
+            return Err(AppError::SupplyExhausted.into());
+        }
+    }
+
+    // the metadata program needs the new mint to exist with exactly one token
+    let shared = MintAccounts {
+        authority_info,
+        signer_info,
+        token_program_info,
+        ass_token_program_info,
+        rent_info,
+        system_info,
+        metadata_program_info,
+    };
+    mint_tokens(
+        program_id,
+        &shared,
+        mint_info,
+        ata_info,
+        signer_info,
+        0,
+        1,
+        authority_bump,
+    )?;
+
+    msg!("Print Edition {}", edition_number);
+    let authority_seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, program_id.as_ref(), &[authority_bump]];
+    invoke_signed(
+        &mint_new_edition_from_master_edition_via_token(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *edition_info.key,
+            *master_edition_info.key,
+            *mint_info.key,
+            *authority_info.key,
+            *signer_info.key,
+            *signer_info.key,
+            *master_token_info.key,
+            *signer_info.key,
+            *master_metadata_info.key,
+            *master_mint_info.key,
+            edition_number,
+        ),
+        &[
+            metadata_info.clone(),
+            edition_info.clone(),
+            master_edition_info.clone(),
+            mint_info.clone(),
+            edition_mark_info.clone(),
+            authority_info.clone(),
+            signer_info.clone(),
+            master_token_info.clone(),
+            master_metadata_info.clone(),
+            token_program_info.clone(),
+            system_info.clone(),
+            rent_info.clone(),
+            metadata_program_info.clone(),
+        ],
+        &[authority_seeds],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use mpl_token_metadata::{
+        pda::find_master_edition_account,
+        state::{Key, MAX_MASTER_EDITION_LEN},
+    };
+    use solana_program::system_program;
+
+    /// Accounts of a PrintEdition of `edition_number` from a master edition
+    /// capped at `max_supply`, held by a fresh signer.
+    fn print_accounts(max_supply: Option<u64>, edition_number: u64) -> Vec<TestAccount> {
+        let signer = Pubkey::new_unique();
+        let master_mint = Pubkey::new_unique();
+        let master_edition = MasterEditionV2 {
+            key: Key::MasterEditionV2,
+            supply: 1,
+            max_supply,
+        };
+        let metadata_program_id = mpl_token_metadata::id();
+        let mark = (edition_number / EDITION_MARKER_BIT_SIZE).to_string();
+        let (edition_mark, _) = Pubkey::find_program_address(
+            &[
+                PREFIX.as_bytes(),
+                metadata_program_id.as_ref(),
+                master_mint.as_ref(),
+                EDITION.as_bytes(),
+                mark.as_bytes(),
+            ],
+            &metadata_program_id,
+        );
+        let metadata = test_metadata(master_mint, signer);
+        let [record, config] = unrecorded(&master_mint);
+        vec![
+            TestAccount::new(
+                pda(&[MINT_AUTHORITY_SEED, crate::id().as_ref()]),
+                crate::id(),
+                vec![],
+            ),
+            TestAccount::wallet(signer).signer(),
+            TestAccount::mint(master_mint, &spl_token::id(), &signer, 0),
+            metadata_account(&metadata),
+            TestAccount::state(
+                find_master_edition_account(&master_mint).0,
+                metadata_program_id,
+                &master_edition,
+                MAX_MASTER_EDITION_LEN,
+            ),
+            TestAccount::token_account(
+                ata(&signer, &master_mint),
+                &spl_token::id(),
+                &master_mint,
+                &signer,
+                1,
+            ),
+            TestAccount::empty(Pubkey::new_unique()).signer(),
+            TestAccount::empty(Pubkey::new_unique()),
+            TestAccount::empty(Pubkey::new_unique()),
+            TestAccount::empty(Pubkey::new_unique()),
+            TestAccount::empty(edition_mark),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(spl_associated_token_account::id()),
+            TestAccount::rent(),
+            TestAccount::program(system_program::id()),
+            TestAccount::program(metadata_program_id),
+            record,
+            config,
+        ]
+    }
+
+    #[test]
+    fn print_edition_rejects_an_edition_over_the_cap() {
+        setup();
+        let mut accounts = print_accounts(Some(1), 2);
+        assert_eq!(
+            process_print_edition(&crate::id(), &infos(&mut accounts), 2),
+            Err(AppError::SupplyExhausted.into())
+        );
+        assert!(invoked().is_empty());
+    }
+}
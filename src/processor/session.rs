@@ -0,0 +1,322 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    state::{Session, SESSION_SEED, STATE_VERSION},
+    utils::*,
+};
+
+pub fn process_create_session(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    session_key: Pubkey,
+    expiry: u64,
+    max_mints: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let player_info = next_account_info(account_info_iter)?;
+    let session_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_info = next_account_info(account_info_iter)?;
+
+    assert_signer(player_info)?;
+    if expiry <= now_timestamp()? {
+        msg!("Session expiry {} is not in the future", expiry);
+        return Err(AppError::SessionExpired.into());
+    }
+    if max_mints == 0 {
+        return Err(AppError::SessionExhausted.into());
+    }
+    let bump = assert_derivation(
+        program_id,
+        session_info,
+        &[SESSION_SEED, player_info.key.as_ref(), session_key.as_ref()],
+    )?;
+    // an existing session of the key just gets its expiry and budget replaced
+    if session_info.data_is_empty() {
+        msg!("Create Session");
+        create_or_allocate_account_raw(
+            *program_id,
+            session_info,
+            rent_info,
+            system_info,
+            player_info,
+            Session::LEN,
+            &[SESSION_SEED, player_info.key.as_ref(), session_key.as_ref(), &[bump]],
+        )?;
+    } else {
+        Session::from_account_info(program_id, session_info)?;
+    }
+    msg!("Session {} until {} for {} mints", session_key, expiry, max_mints);
+    let session = Session {
+        player: *player_info.key,
+        session_key,
+        expiry,
+        remaining_mints: max_mints,
+        bump,
+        version: STATE_VERSION,
+    };
+    session.serialize(&mut *session_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Closes the session early, refunding its rent to the player.
+pub fn process_revoke_session(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    session_key: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let player_info = next_account_info(account_info_iter)?;
+    let session_info = next_account_info(account_info_iter)?;
+
+    assert_signer(player_info)?;
+    assert_derivation(
+        program_id,
+        session_info,
+        &[SESSION_SEED, player_info.key.as_ref(), session_key.as_ref()],
+    )?;
+    assert_owned_by(session_info, program_id)?;
+
+    msg!("Revoke Session {}", session_key);
+    session_info.data.borrow_mut().fill(0);
+    let lamports = session_info.lamports();
+    **session_info.lamports.borrow_mut() = 0;
+    **player_info.lamports.borrow_mut() = checked_add(player_info.lamports(), lamports)?;
+    Ok(())
+}
+
+/// Takes one mint from the budget of the session letting `signer_info` mint
+/// for `player_info`. The session is derived from both, so a key can't spend
+/// another player's session.
+pub fn use_session(
+    program_id: &Pubkey,
+    session_info: &AccountInfo,
+    signer_info: &AccountInfo,
+    player_info: &AccountInfo,
+) -> ProgramResult {
+    assert_derivation(
+        program_id,
+        session_info,
+        &[SESSION_SEED, player_info.key.as_ref(), signer_info.key.as_ref()],
+    )?;
+    if session_info.owner != program_id || session_info.data_is_empty() {
+        return Err(AppError::InvalidSession.into());
+    }
+    let mut session = Session::from_account_info(program_id, session_info)?;
+    if now_timestamp()? >= session.expiry {
+        return Err(AppError::SessionExpired.into());
+    }
+    session.remaining_mints = session
+        .remaining_mints
+        .checked_sub(1)
+        .ok_or(AppError::SessionExhausted)?;
+    msg!("Session mint for {}, {} left", player_info.key, session.remaining_mints);
+    session.serialize(&mut *session_info.data.borrow_mut())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        processor::process_mint,
+        state::{WalletCounter, WALLET_COUNTER_SEED},
+        test_utils::*,
+    };
+    use mpl_token_metadata::instruction::MetadataInstruction;
+    use solana_program::{program_error::ProgramError, system_program};
+
+    const NOW: u64 = 1_000_000;
+
+    fn session_address(player: &Pubkey, session_key: &Pubkey) -> Pubkey {
+        pda(&[SESSION_SEED, player.as_ref(), session_key.as_ref()])
+    }
+
+    fn session_account(player: Pubkey, session_key: Pubkey, remaining_mints: u64) -> TestAccount {
+        let session = Session {
+            player,
+            session_key,
+            expiry: NOW + 60,
+            remaining_mints,
+            bump: 0,
+            version: STATE_VERSION,
+        };
+        let key = session_address(&player, &session_key);
+        TestAccount::state(key, crate::id(), &session, Session::LEN)
+    }
+
+    fn load_session(account: &TestAccount) -> Session {
+        try_from_slice_unchecked(&account.data).unwrap()
+    }
+
+    /// CreateSession accounts of `player` for `session_key`, not created yet.
+    fn create_accounts(player: Pubkey, session_key: Pubkey) -> Vec<TestAccount> {
+        vec![
+            TestAccount::wallet(player).signer(),
+            TestAccount::new(
+                session_address(&player, &session_key),
+                crate::id(),
+                vec![0; Session::LEN],
+            ),
+            TestAccount::rent(),
+            TestAccount::program(system_program::id()),
+        ]
+    }
+
+    /// Mint accounts signed by `session_key` for `player`, with `session`.
+    fn session_mint(player: Pubkey, session_key: Pubkey, session: TestAccount) -> Vec<TestAccount> {
+        let config = test_config(Pubkey::new_unique());
+        let mut accounts = mint_accounts(&config, session_key);
+        set_mint_recipient(&mut accounts, player);
+        accounts[mint_slot::SESSION] = session;
+        accounts
+    }
+
+    #[test]
+    fn create_session_stores_expiry_and_budget() {
+        setup();
+        let (player, session_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = create_accounts(player, session_key);
+        process_create_session(&crate::id(), &infos(&mut accounts), session_key, NOW + 60, 3)
+            .unwrap();
+
+        let session = load_session(&accounts[1]);
+        assert_eq!(
+            (session.player, session.session_key, session.expiry, session.remaining_mints),
+            (player, session_key, NOW + 60, 3)
+        );
+        assert_eq!(session.version, STATE_VERSION);
+    }
+
+    #[test]
+    fn create_session_refuses_an_expired_or_empty_session() {
+        setup();
+        let (player, session_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = create_accounts(player, session_key);
+        assert_eq!(
+            process_create_session(&crate::id(), &infos(&mut accounts), session_key, NOW, 3),
+            Err(AppError::SessionExpired.into())
+        );
+        assert_eq!(
+            process_create_session(&crate::id(), &infos(&mut accounts), session_key, NOW + 60, 0),
+            Err(AppError::SessionExhausted.into())
+        );
+    }
+
+    #[test]
+    fn create_session_needs_the_player_signature() {
+        setup();
+        let (player, session_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = create_accounts(player, session_key);
+        accounts[0] = TestAccount::wallet(player);
+        assert_eq!(
+            process_create_session(&crate::id(), &infos(&mut accounts), session_key, NOW + 60, 3),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+
+    #[test]
+    fn revoke_session_refunds_the_player() {
+        setup();
+        let (player, session_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = vec![
+            TestAccount::wallet(player).signer(),
+            session_account(player, session_key, 3),
+        ];
+        let refund = accounts[0].lamports + accounts[1].lamports;
+        process_revoke_session(&crate::id(), &infos(&mut accounts), session_key).unwrap();
+
+        assert_eq!(accounts[0].lamports, refund);
+        assert_eq!(accounts[1].lamports, 0);
+        assert!(accounts[1].data.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn session_key_mints_for_its_player() {
+        setup();
+        let (player, session_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = session_mint(player, session_key, session_account(player, session_key, 2));
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        process_mint(&crate::id(), &infos(&mut accounts), args).unwrap();
+
+        assert_eq!(load_session(&accounts[mint_slot::SESSION]).remaining_mints, 1);
+        assert_eq!(accounts[mint_slot::ATA].key, ata(&player, &accounts[mint_slot::MINT].key));
+        assert_eq!(load_record(&accounts[mint_slot::RECORD]).minter, player);
+        // the player, not the short-lived key, ends up creator and update authority
+        let creators = created_metadata()[0].creators.clone().unwrap();
+        assert_eq!(creators[0].address, player);
+        assert!(!creators[0].verified);
+        assert!(metadata_calls().iter().any(|call| matches!(
+            call,
+            MetadataInstruction::UpdateMetadataAccountV2(args)
+                if args.update_authority == Some(player)
+        )));
+    }
+
+    #[test]
+    fn session_mint_counts_against_the_player() {
+        setup();
+        let (player, session_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut config = test_config(Pubkey::new_unique());
+        config.max_per_wallet = 1;
+        let mut accounts = mint_accounts(&config, session_key);
+        set_mint_recipient(&mut accounts, player);
+        accounts[mint_slot::SESSION] = session_account(player, session_key, 2);
+        let config_key = accounts[mint_slot::CONFIG].key;
+        let counter = WalletCounter {
+            count: 1,
+            bump: 0,
+            version: STATE_VERSION,
+        };
+        accounts[mint_slot::COUNTER] = TestAccount::state(
+            pda(&[WALLET_COUNTER_SEED, config_key.as_ref(), player.as_ref()]),
+            crate::id(),
+            &counter,
+            WalletCounter::LEN,
+        );
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::WalletLimitReached.into())
+        );
+    }
+
+    #[test]
+    fn session_mint_refuses_expired_exhausted_and_revoked_sessions() {
+        let (player, session_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let revoked = TestAccount::empty(session_address(&player, &session_key));
+        let cases = [
+            (session_account(player, session_key, 2), NOW + 60, AppError::SessionExpired),
+            (session_account(player, session_key, 0), NOW, AppError::SessionExhausted),
+            (revoked, NOW, AppError::InvalidSession),
+        ];
+        for (session, now, error) in cases {
+            setup();
+            set_clock(100, now as i64);
+            let mut accounts = session_mint(player, session_key, session);
+            let args = test_mint_args(accounts[mint_slot::MINT].key);
+            assert_eq!(process_mint(&crate::id(), &infos(&mut accounts), args), Err(error.into()));
+            assert!(invoked().is_empty());
+        }
+    }
+
+    #[test]
+    fn session_key_cannot_mint_for_another_player() {
+        setup();
+        let (player, session_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let other = Pubkey::new_unique();
+        let mut accounts = session_mint(other, session_key, session_account(player, session_key, 2));
+        let args = test_mint_args(accounts[mint_slot::MINT].key);
+        assert_eq!(
+            process_mint(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::InvalidDerivedKey.into())
+        );
+    }
+}
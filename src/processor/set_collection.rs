@@ -0,0 +1,99 @@
+use mpl_token_metadata::{
+    instruction::set_and_verify_sized_collection_item,
+    state::{Metadata, EDITION, PREFIX},
+};
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+};
+
+use crate::{processor::config::assert_not_sealed, utils::*};
+
+pub fn process_set_collection(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let collection_mint_info = next_account_info(account_info_iter)?;
+    let collection_metadata_info = next_account_info(account_info_iter)?;
+    let collection_edition_info = next_account_info(account_info_iter)?;
+    let collection_authority_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_signer(collection_authority_info)?;
+    assert_metadata_program(metadata_program_info)?;
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    let metadata = Metadata::from_account_info(metadata_info)?;
+    assert_not_sealed(program_id, &metadata.mint, record_info, config_info)?;
+    assert_collection_accounts(
+        collection_mint_info,
+        collection_metadata_info,
+        collection_edition_info,
+    )?;
+
+    // the collection authority is also the update authority of the NFT and
+    // pays for any resize of the collection details
+    msg!("Set And Verify Collection");
+    invoke(
+        &set_and_verify_sized_collection_item(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *collection_authority_info.key,
+            *collection_authority_info.key,
+            *collection_authority_info.key,
+            *collection_mint_info.key,
+            *collection_metadata_info.key,
+            *collection_edition_info.key,
+            None,
+        ),
+        &[
+            metadata_info.clone(),
+            collection_authority_info.clone(),
+            collection_mint_info.clone(),
+            collection_metadata_info.clone(),
+            collection_edition_info.clone(),
+            metadata_program_info.clone(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Checks the collection mint, metadata and master edition belong together,
+/// re-deriving both PDAs under the metadata program.
+pub fn assert_collection_accounts(
+    collection_mint_info: &AccountInfo,
+    collection_metadata_info: &AccountInfo,
+    collection_edition_info: &AccountInfo,
+) -> ProgramResult {
+    assert_owned_by(collection_mint_info, &spl_token::id())?;
+    assert_owned_by(collection_metadata_info, &mpl_token_metadata::id())?;
+    assert_owned_by(collection_edition_info, &mpl_token_metadata::id())?;
+
+    let metadata_program_id = mpl_token_metadata::id();
+    assert_derivation(
+        &metadata_program_id,
+        collection_metadata_info,
+        &[
+            PREFIX.as_bytes(),
+            metadata_program_id.as_ref(),
+            collection_mint_info.key.as_ref(),
+        ],
+    )?;
+    assert_derivation(
+        &metadata_program_id,
+        collection_edition_info,
+        &[
+            PREFIX.as_bytes(),
+            metadata_program_id.as_ref(),
+            collection_mint_info.key.as_ref(),
+            EDITION.as_bytes(),
+        ],
+    )?;
+    Ok(())
+}
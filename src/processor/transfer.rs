@@ -0,0 +1,172 @@
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+};
+use crate::{error::AppError, state::RECORD_SEED, utils::*};
+
+/// Moves `amount` tokens of the signer. Allowed after a seal, see
+/// `assert_not_sealed`.
+pub fn process_transfer(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let source_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_signer(authority_info)?;
+    let token_program = TokenProgram::from_account_info(token_program_info)?;
+    assert_owned_by(source_info, token_program.id())?;
+
+    msg!("Transfer {}", amount);
+    spl_token_transfer(
+        &token_program,
+        source_info.clone(),
+        mint_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        amount,
+        &[],
+    )
+}
+
+/// Moves a program-minted NFT to `destination_wallet_info`, creating its
+/// associated token account when needed. Frozen (soulbound) NFTs can't move,
+/// sealed ones can.
+pub fn process_transfer_nft(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let destination_wallet_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let ass_token_program_info = next_account_info(account_info_iter)?;
+    let system_info = next_account_info(account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    let token_program = TokenProgram::from_account_info(token_program_info)?;
+    assert_eq_pubkey(ass_token_program_info, &spl_associated_token_account::id())?;
+    // only mints with a record were created by this program
+    assert_owned_by(record_info, program_id)?;
+    assert_derivation(program_id, record_info, &[RECORD_SEED, mint_info.key.as_ref()])?;
+
+    let source = assert_token_account(&token_program, source_info, owner_info.key, mint_info.key)?;
+    if source.is_frozen() {
+        return Err(AppError::NonTransferable.into());
+    }
+    if *destination_info.key
+        != token_program.associated_token_address(destination_wallet_info.key, mint_info.key)
+    {
+        return Err(AppError::InvalidAssociatedAddress.into());
+    }
+
+    if destination_info.data_is_empty() || destination_info.lamports() == 0 {
+        msg!("Create Associated Token Account");
+        invoke(
+            &token_program.create_associated_token_account(
+                owner_info.key,
+                destination_wallet_info.key,
+                mint_info.key,
+            ),
+            &[
+                owner_info.clone(),
+                destination_info.clone(),
+                destination_wallet_info.clone(),
+                mint_info.clone(),
+                system_info.clone(),
+                token_program_info.clone(),
+                ass_token_program_info.clone(),
+            ],
+        )?;
+    }
+
+    msg!("Transfer NFT");
+    spl_token_transfer(
+        &token_program,
+        source_info.clone(),
+        mint_info.clone(),
+        destination_info.clone(),
+        owner_info.clone(),
+        1,
+        &[],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{state::MINT_AUTHORITY_SEED, test_utils::*};
+    use solana_program::{program_pack::Pack, system_program};
+    use spl_token::state::{Account, AccountState};
+
+    /// Accounts of a TransferNft of a program-minted NFT held by a fresh
+    /// owner to a wallet without a token account for it yet.
+    fn transfer_nft_accounts() -> Vec<TestAccount> {
+        let (owner, mint, destination) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let authority = pda(&[MINT_AUTHORITY_SEED, crate::id().as_ref()]);
+        vec![
+            TestAccount::wallet(owner).signer(),
+            TestAccount::token_account(ata(&owner, &mint), &spl_token::id(), &mint, &owner, 1),
+            TestAccount::mint(mint, &spl_token::id(), &authority, 0),
+            TestAccount::new(pda(&[RECORD_SEED, mint.as_ref()]), crate::id(), vec![]),
+            TestAccount::wallet(destination),
+            TestAccount::empty(ata(&destination, &mint)),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(spl_associated_token_account::id()),
+            TestAccount::program(system_program::id()),
+        ]
+    }
+
+    #[test]
+    fn transfer_nft_creates_the_token_account_of_a_new_wallet() {
+        setup();
+        let mut accounts = transfer_nft_accounts();
+        process_transfer_nft(&crate::id(), &infos(&mut accounts)).unwrap();
+
+        let calls = invoked();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].instruction.program_id, spl_associated_token_account::id());
+        assert_eq!(calls[1].instruction.program_id, spl_token::id());
+        let transfer = spl_token::instruction::TokenInstruction::unpack(&calls[1].instruction.data);
+        assert_eq!(
+            transfer,
+            Ok(spl_token::instruction::TokenInstruction::TransferChecked { amount: 1, decimals: 0 })
+        );
+        assert_eq!(calls[1].instruction.accounts[2].pubkey, accounts[5].key);
+    }
+
+    #[test]
+    fn transfer_nft_refuses_a_frozen_token() {
+        setup();
+        let mut accounts = transfer_nft_accounts();
+        let mut source = Account::unpack(&accounts[1].data).unwrap();
+        source.state = AccountState::Frozen;
+        source.pack_into_slice(&mut accounts[1].data);
+        assert_eq!(
+            process_transfer_nft(&crate::id(), &infos(&mut accounts)),
+            Err(AppError::NonTransferable.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn transfer_nft_refuses_another_destination_account() {
+        setup();
+        let mut accounts = transfer_nft_accounts();
+        accounts[5] = TestAccount::empty(Pubkey::new_unique());
+        assert_eq!(
+            process_transfer_nft(&crate::id(), &infos(&mut accounts)),
+            Err(AppError::InvalidAssociatedAddress.into())
+        );
+    }
+}
@@ -0,0 +1,452 @@
+use mpl_token_metadata::{
+    instruction::{update_metadata_accounts_v2, update_primary_sale_happened_via_token},
+    state::{Creator, DataV2, Metadata, MAX_NAME_LENGTH, MAX_URI_LENGTH},
+};
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    instruction::{CreatorArgs, UpdateMetadataArgs},
+    processor::{config::assert_not_sealed, mint::assert_nft_token_program},
+    utils::*,
+};
+
+pub fn process_update_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: UpdateMetadataArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let update_authority_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_signer(update_authority_info)?;
+    assert_metadata_program(metadata_program_info)?;
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    let metadata: Metadata = try_from_slice_unchecked(&metadata_info.data.borrow())?;
+    if metadata.update_authority != *update_authority_info.key {
+        return Err(AppError::NotUpdateAuthority.into());
+    }
+    assert_not_sealed(program_id, &metadata.mint, record_info, config_info)?;
+    if !metadata.is_mutable {
+        return Err(AppError::MetadataImmutable.into());
+    }
+
+    // metadata strings are stored padded with zero bytes
+    let uri = match args.new_uri {
+        Some(uri) => uri,
+        None => metadata.data.uri.trim_end_matches('\0').to_string(),
+    };
+    if uri.is_empty() {
+        return Err(AppError::InvalidUri.into());
+    }
+    if uri.len() > MAX_URI_LENGTH {
+        return Err(AppError::UriTooLong.into());
+    }
+    let name = match args.new_name {
+        Some(name) => name,
+        None => metadata.data.name.trim_end_matches('\0').to_string(),
+    };
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(AppError::NameTooLong.into());
+    }
+    let seller_fee_basis_points = args
+        .new_seller_fee_basis_points
+        .unwrap_or(metadata.data.seller_fee_basis_points);
+    if seller_fee_basis_points > 10_000 {
+        return Err(AppError::InvalidRoyalty.into());
+    }
+    let creators = match args.new_creators {
+        Some(new_creators) => override_creators(
+            metadata.data.creators.as_deref().unwrap_or_default(),
+            new_creators,
+            update_authority_info.key,
+            seller_fee_basis_points,
+        )?,
+        None => metadata.data.creators,
+    };
+
+    msg!("Update Metadata Account");
+    invoke(
+        &update_metadata_accounts_v2(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *update_authority_info.key,
+            None,
+            Some(DataV2 {
+                name,
+                symbol: metadata.data.symbol.trim_end_matches('\0').to_string(),
+                uri,
+                seller_fee_basis_points,
+                creators,
+                collection: metadata.collection,
+                uses: metadata.uses,
+            }),
+            None,
+            None,
+        ),
+        &[
+            metadata_info.clone(),
+            update_authority_info.clone(),
+            metadata_program_info.clone(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Validates a replacement creator list. The metadata program only keeps a
+/// creator verified if it already was, unless it is the signing update
+/// authority, so the flags are carried over from `existing`.
+fn override_creators(
+    existing: &[Creator],
+    new_creators: Vec<CreatorArgs>,
+    update_authority: &Pubkey,
+    seller_fee_basis_points: u16,
+) -> Result<Option<Vec<Creator>>, ProgramError> {
+    if new_creators.is_empty() {
+        if seller_fee_basis_points > 0 {
+            return Err(AppError::RoyaltyWithoutCreators.into());
+        }
+        return Ok(None);
+    }
+    validate_creators(&new_creators)?;
+    Ok(Some(
+        new_creators
+            .into_iter()
+            .map(|creator| Creator {
+                address: creator.address,
+                verified: creator.address == *update_authority
+                    || existing
+                        .iter()
+                        .any(|other| other.address == creator.address && other.verified),
+                share: creator.share,
+            })
+            .collect(),
+    ))
+}
+
+/// Hands the update authority of a metadata to another key, e.g. a multisig
+/// after launch. Nothing else on the metadata changes.
+pub fn process_transfer_update_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let update_authority_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_signer(update_authority_info)?;
+    assert_metadata_program(metadata_program_info)?;
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    let metadata = Metadata::from_account_info(metadata_info)?;
+    assert_eq_pubkey(update_authority_info, &metadata.update_authority)
+        .map_err(|_| AppError::NotUpdateAuthority)?;
+    assert_not_sealed(program_id, &metadata.mint, record_info, config_info)?;
+
+    msg!("Transfer Update Authority");
+    invoke(
+        &update_metadata_accounts_v2(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *update_authority_info.key,
+            Some(new_authority),
+            None,
+            None,
+            None,
+        ),
+        &[
+            metadata_info.clone(),
+            update_authority_info.clone(),
+            metadata_program_info.clone(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Sets primary_sale_happened once the NFT is in the hands of its holder. The
+/// metadata program accepts the flag from the owner of the token account.
+pub fn process_set_primary_sale(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    assert_metadata_program(metadata_program_info)?;
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    let metadata = Metadata::from_account_info(metadata_info)?;
+    let token_program = assert_nft_token_program(token_program_info)?;
+    let token_account =
+        assert_token_account(&token_program, token_account_info, owner_info.key, &metadata.mint)?;
+    if token_account.amount != 1 {
+        return Err(AppError::InvalidTokenAmount.into());
+    }
+    assert_not_sealed(program_id, &metadata.mint, record_info, config_info)?;
+
+    msg!("Set Primary Sale Happened");
+    invoke(
+        &update_primary_sale_happened_via_token(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *owner_info.key,
+            *token_account_info.key,
+        ),
+        &[
+            metadata_info.clone(),
+            owner_info.clone(),
+            token_account_info.clone(),
+            metadata_program_info.clone(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Replaces only the uri of a metadata, e.g. for a reveal.
+pub fn process_update_uri(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_uri: String,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let update_authority_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_signer(update_authority_info)?;
+    assert_metadata_program(metadata_program_info)?;
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    let metadata = Metadata::from_account_info(metadata_info)?;
+    assert_eq_pubkey(update_authority_info, &metadata.update_authority)
+        .map_err(|_| AppError::NotUpdateAuthority)?;
+    assert_not_sealed(program_id, &metadata.mint, record_info, config_info)?;
+    if !metadata.is_mutable {
+        return Err(AppError::MetadataImmutable.into());
+    }
+    if new_uri.is_empty() {
+        return Err(AppError::InvalidUri.into());
+    }
+    if new_uri.len() > MAX_URI_LENGTH {
+        return Err(AppError::UriTooLong.into());
+    }
+
+    msg!("Update Uri");
+    invoke(
+        &update_metadata_accounts_v2(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *update_authority_info.key,
+            None,
+            Some(DataV2 {
+                name: metadata.data.name.trim_end_matches('\0').to_string(),
+                symbol: metadata.data.symbol.trim_end_matches('\0').to_string(),
+                uri: new_uri,
+                seller_fee_basis_points: metadata.data.seller_fee_basis_points,
+                creators: metadata.data.creators,
+                collection: metadata.collection,
+                uses: metadata.uses,
+            }),
+            None,
+            None,
+        ),
+        &[
+            metadata_info.clone(),
+            update_authority_info.clone(),
+            metadata_program_info.clone(),
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshDeserialize;
+    use mpl_token_metadata::instruction::MetadataInstruction;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn update_metadata_accounts(metadata: &Metadata) -> Vec<TestAccount> {
+        let mut accounts = vec![
+            TestAccount::wallet(metadata.update_authority).signer(),
+            metadata_account(metadata),
+            TestAccount::program(mpl_token_metadata::id()),
+        ];
+        accounts.extend(unrecorded(&metadata.mint));
+        accounts
+    }
+
+    fn update_args() -> UpdateMetadataArgs {
+        UpdateMetadataArgs {
+            new_uri: None,
+            new_name: None,
+            new_seller_fee_basis_points: None,
+            new_creators: None,
+        }
+    }
+
+    /// Data the recorded update_metadata_accounts_v2 CPI writes.
+    fn updated_data() -> DataV2 {
+        let calls = invoked_program(&mpl_token_metadata::id());
+        assert_eq!(calls.len(), 1);
+        match MetadataInstruction::try_from_slice(&calls[0].instruction.data).unwrap() {
+            MetadataInstruction::UpdateMetadataAccountV2(args) => args.data.unwrap(),
+            _ => panic!("not an update_metadata_accounts_v2"),
+        }
+    }
+
+    #[test]
+    fn update_metadata_changes_only_the_uri() {
+        setup();
+        let metadata = test_metadata(Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = update_metadata_accounts(&metadata);
+        let args = UpdateMetadataArgs {
+            new_uri: Some(String::from("https://example.com/fixed.json")),
+            ..update_args()
+        };
+        process_update_metadata(&crate::id(), &infos(&mut accounts), args).unwrap();
+
+        let data = updated_data();
+        assert_eq!(data.uri, "https://example.com/fixed.json");
+        assert_eq!(data.name, metadata.data.name);
+        assert_eq!(data.seller_fee_basis_points, metadata.data.seller_fee_basis_points);
+        assert_eq!(data.creators, metadata.data.creators);
+    }
+
+    #[test]
+    fn update_metadata_changes_only_the_name() {
+        setup();
+        let metadata = test_metadata(Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = update_metadata_accounts(&metadata);
+        let args = UpdateMetadataArgs {
+            new_name: Some(String::from("Calm squirrel")),
+            ..update_args()
+        };
+        process_update_metadata(&crate::id(), &infos(&mut accounts), args).unwrap();
+
+        let data = updated_data();
+        assert_eq!(data.name, "Calm squirrel");
+        assert_eq!(data.uri, metadata.data.uri);
+    }
+
+    #[test]
+    fn update_metadata_rejects_another_authority() {
+        setup();
+        let metadata = test_metadata(Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = update_metadata_accounts(&metadata);
+        accounts[0] = TestAccount::wallet(Pubkey::new_unique()).signer();
+        let args = UpdateMetadataArgs { new_uri: Some(String::from("x")), ..update_args() };
+        assert_eq!(
+            process_update_metadata(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::NotUpdateAuthority.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn update_metadata_requires_the_authority_signature() {
+        setup();
+        let metadata = test_metadata(Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = update_metadata_accounts(&metadata);
+        accounts[0].is_signer = false;
+        assert_eq!(
+            process_update_metadata(&crate::id(), &infos(&mut accounts), update_args()),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+
+    #[test]
+    fn update_metadata_rejects_immutable_metadata() {
+        setup();
+        let mut metadata = test_metadata(Pubkey::new_unique(), Pubkey::new_unique());
+        metadata.is_mutable = false;
+        let mut accounts = update_metadata_accounts(&metadata);
+        assert_eq!(
+            process_update_metadata(&crate::id(), &infos(&mut accounts), update_args()),
+            Err(AppError::MetadataImmutable.into())
+        );
+    }
+
+    #[test]
+    fn royalty_override_replaces_the_creators() {
+        setup();
+        let metadata = test_metadata(Pubkey::new_unique(), Pubkey::new_unique());
+        let artist = Pubkey::new_unique();
+        let mut accounts = update_metadata_accounts(&metadata);
+        let args = UpdateMetadataArgs {
+            new_seller_fee_basis_points: Some(1_000),
+            new_creators: Some(vec![
+                CreatorArgs {
+                    address: metadata.update_authority,
+                    share: 30,
+                    provenance_only: false,
+                },
+                CreatorArgs { address: artist, share: 70, provenance_only: false },
+            ]),
+            ..update_args()
+        };
+        process_update_metadata(&crate::id(), &infos(&mut accounts), args).unwrap();
+
+        let data = updated_data();
+        assert_eq!(data.seller_fee_basis_points, 1_000);
+        let creators = data.creators.unwrap();
+        assert_eq!(
+            creators.iter().map(|c| (c.address, c.share, c.verified)).collect::<Vec<_>>(),
+            [(metadata.update_authority, 30, true), (artist, 70, false)]
+        );
+    }
+
+    #[test]
+    fn royalty_override_rejects_invalid_creators() {
+        setup();
+        let metadata = test_metadata(Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = update_metadata_accounts(&metadata);
+        let args = UpdateMetadataArgs {
+            new_creators: Some(vec![CreatorArgs {
+                address: metadata.update_authority,
+                share: 90,
+                provenance_only: false,
+            }]),
+            ..update_args()
+        };
+        assert_eq!(
+            process_update_metadata(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::InvalidCreatorShares.into())
+        );
+        assert!(invoked().is_empty());
+    }
+
+    #[test]
+    fn royalty_override_needs_a_creator_for_a_royalty() {
+        setup();
+        let metadata = test_metadata(Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = update_metadata_accounts(&metadata);
+        let args = UpdateMetadataArgs { new_creators: Some(vec![]), ..update_args() };
+        assert_eq!(
+            process_update_metadata(&crate::id(), &infos(&mut accounts), args),
+            Err(AppError::RoyaltyWithoutCreators.into())
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use mpl_token_metadata::{
+    instruction::{verify_collection, verify_sized_collection_item},
+    state::Metadata,
+};
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    processor::{config::assert_not_sealed, set_collection::assert_collection_accounts},
+    utils::*,
+};
+
+pub fn process_verify_collection(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let collection_authority_info = next_account_info(account_info_iter)?;
+    let collection_mint_info = next_account_info(account_info_iter)?;
+    let collection_metadata_info = next_account_info(account_info_iter)?;
+    let collection_edition_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_signer(collection_authority_info)?;
+    assert_metadata_program(metadata_program_info)?;
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    assert_collection_accounts(
+        collection_mint_info,
+        collection_metadata_info,
+        collection_edition_info,
+    )?;
+
+    // a failed CPI aborts the transaction, so the metadata program's own
+    // checks are repeated here to fail with a readable error instead
+    let metadata = Metadata::from_account_info(metadata_info)?;
+    assert_not_sealed(program_id, &metadata.mint, record_info, config_info)?;
+    let collection_metadata = Metadata::from_account_info(collection_metadata_info)?;
+    match metadata.collection {
+        Some(collection) if collection.key != *collection_mint_info.key => {
+            msg!("NFT belongs to collection {}", collection.key);
+            return Err(AppError::CollectionVerifyFailed.into());
+        }
+        Some(collection) if collection.verified => {
+            msg!("NFT is already verified in collection {}", collection.key);
+            return Err(AppError::CollectionVerifyFailed.into());
+        }
+        Some(_) => {}
+        None => {
+            msg!("NFT has no collection, see SetCollection");
+            return Err(AppError::CollectionVerifyFailed.into());
+        }
+    }
+    if collection_metadata.update_authority != *collection_authority_info.key {
+        msg!("Collection authority is {}", collection_metadata.update_authority);
+        return Err(AppError::CollectionVerifyFailed.into());
+    }
+
+    // sized collections track their item count and only accept the sized variant
+    let verify = if collection_metadata.collection_details.is_some() {
+        verify_sized_collection_item
+    } else {
+        verify_collection
+    };
+
+    // the collection authority pays for the verification as well
+    msg!("Verify Collection");
+    invoke(
+        &verify(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *collection_authority_info.key,
+            *collection_authority_info.key,
+            *collection_mint_info.key,
+            *collection_metadata_info.key,
+            *collection_edition_info.key,
+            None,
+        ),
+        &[
+            metadata_info.clone(),
+            collection_authority_info.clone(),
+            collection_mint_info.clone(),
+            collection_metadata_info.clone(),
+            collection_edition_info.clone(),
+            metadata_program_info.clone(),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use mpl_token_metadata::{
+        instruction::MetadataInstruction,
+        pda::find_master_edition_account,
+        state::{Collection, MAX_MASTER_EDITION_LEN},
+    };
+
+    /// VerifyCollection accounts of an NFT whose collection field is `claim`
+    /// of the collection mint, signed by the collection's update authority.
+    fn verify_accounts(claim: fn(Pubkey) -> Option<Collection>) -> Vec<TestAccount> {
+        let authority = Pubkey::new_unique();
+        let collection_mint = Pubkey::new_unique();
+        let mut item = test_metadata(Pubkey::new_unique(), Pubkey::new_unique());
+        item.collection = claim(collection_mint);
+        let [record, config] = unrecorded(&item.mint);
+        vec![
+            metadata_account(&item),
+            TestAccount::wallet(authority).signer(),
+            TestAccount::mint(collection_mint, &spl_token::id(), &authority, 0),
+            metadata_account(&test_metadata(collection_mint, authority)),
+            TestAccount::new(
+                find_master_edition_account(&collection_mint).0,
+                mpl_token_metadata::id(),
+                vec![0; MAX_MASTER_EDITION_LEN],
+            ),
+            TestAccount::program(mpl_token_metadata::id()),
+            record,
+            config,
+        ]
+    }
+
+    fn unverified(key: Pubkey) -> Option<Collection> {
+        Some(Collection { key, verified: false })
+    }
+
+    #[test]
+    fn verify_collection_verifies_a_claimed_item() {
+        setup();
+        let mut accounts = verify_accounts(unverified);
+        process_verify_collection(&crate::id(), &infos(&mut accounts)).unwrap();
+        assert!(matches!(
+            metadata_calls()[..],
+            [MetadataInstruction::VerifyCollection]
+        ));
+    }
+
+    #[test]
+    fn verify_collection_rejects_items_it_cannot_verify() {
+        let cases: [fn(Pubkey) -> Option<Collection>; 3] = [
+            |_| None,
+            |_| unverified(Pubkey::new_unique()),
+            |key| Some(Collection { key, verified: true }),
+        ];
+        for claim in cases {
+            setup();
+            let mut accounts = verify_accounts(claim);
+            assert_eq!(
+                process_verify_collection(&crate::id(), &infos(&mut accounts)),
+                Err(AppError::CollectionVerifyFailed.into())
+            );
+            assert!(invoked().is_empty());
+        }
+    }
+
+    #[test]
+    fn verify_collection_needs_the_collection_update_authority() {
+        setup();
+        let mut accounts = verify_accounts(unverified);
+        accounts[1] = TestAccount::wallet(Pubkey::new_unique()).signer();
+        assert_eq!(
+            process_verify_collection(&crate::id(), &infos(&mut accounts)),
+            Err(AppError::CollectionVerifyFailed.into())
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use mpl_token_metadata::{instruction::sign_metadata, state::Metadata};
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+};
+
+use crate::{error::AppError, processor::config::assert_not_sealed, utils::*};
+
+pub fn process_verify_creator(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let creator_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+    let record_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_signer(creator_info)?;
+    assert_metadata_program(metadata_program_info)?;
+    assert_owned_by(metadata_info, &mpl_token_metadata::id())?;
+    let metadata = Metadata::from_account_info(metadata_info)?;
+    assert_not_sealed(program_id, &metadata.mint, record_info, config_info)?;
+    let listed = metadata
+        .data
+        .creators
+        .iter()
+        .flatten()
+        .any(|creator| creator.address == *creator_info.key);
+    if !listed {
+        return Err(AppError::InvalidEqPubkey.into());
+    }
+
+    msg!("Sign Metadata");
+    invoke(
+        &sign_metadata(
+            *metadata_program_info.key,
+            *metadata_info.key,
+            *creator_info.key,
+        ),
+        &[
+            metadata_info.clone(),
+            creator_info.clone(),
+            metadata_program_info.clone(),
+        ],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::set_return_data,
+    pubkey::Pubkey,
+};
+
+use crate::state::{account_state_version, STATE_VERSION};
+
+/// Return data of GetVersion.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct VersionReport {
+    /// highest state version this build reads
+    pub supported: u8,
+    /// highest version byte among the passed state accounts, 0 for none
+    pub highest_observed: u8,
+}
+
+/// Lets ops check a binary against live accounts before a rollback. Accounts
+/// that aren't ours or aren't state are skipped.
+pub fn process_get_version(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let highest_observed = accounts
+        .iter()
+        .filter(|account_info| account_info.owner == program_id)
+        .filter_map(|account_info| account_state_version(&account_info.data.borrow()))
+        .max()
+        .unwrap_or(0);
+
+    msg!("Get Version {} {}", STATE_VERSION, highest_observed);
+    let report = VersionReport {
+        supported: STATE_VERSION,
+        highest_observed,
+    };
+    set_return_data(&report.try_to_vec()?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::{MintRecord, WalletCounter},
+        test_utils::*,
+    };
+
+    #[test]
+    fn get_version_reports_the_highest_state_version() {
+        setup();
+        let config = test_config(Pubkey::new_unique());
+        let mut newer = vec![0; MintRecord::LEN];
+        newer[MintRecord::VERSION_OFFSET] = STATE_VERSION + 1;
+        let mut foreign = vec![0; WalletCounter::LEN];
+        foreign[WalletCounter::VERSION_OFFSET] = STATE_VERSION + 2;
+        let mut accounts = vec![
+            config_account(&config),
+            TestAccount::new(Pubkey::new_unique(), crate::id(), newer),
+            TestAccount::new(Pubkey::new_unique(), Pubkey::new_unique(), foreign),
+            TestAccount::wallet(Pubkey::new_unique()),
+        ];
+        process_get_version(&crate::id(), &infos(&mut accounts)).unwrap();
+
+        let report = VersionReport::try_from_slice(&return_data().unwrap()).unwrap();
+        assert_eq!(
+            report,
+            VersionReport { supported: STATE_VERSION, highest_observed: STATE_VERSION + 1 }
+        );
+    }
+
+    #[test]
+    fn get_version_without_state_accounts_observes_nothing() {
+        setup();
+        process_get_version(&crate::id(), &[]).unwrap();
+        let report = VersionReport::try_from_slice(&return_data().unwrap()).unwrap();
+        assert_eq!(report.highest_observed, 0);
+    }
+}
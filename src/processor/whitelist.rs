@@ -0,0 +1,133 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{AccountInfo, next_account_info},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    processor::config::assert_config_authority,
+    state::{CollectionConfig, WhitelistEntry, STATE_VERSION, WHITELIST_SEED},
+    utils::*,
+};
+
+pub fn process_add_to_whitelist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    wallet: Pubkey,
+    allocation: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let whitelist_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_info = next_account_info(account_info_iter)?;
+
+    assert_config_authority(program_id, config_info, authority_info)?;
+    let bump = assert_derivation(
+        program_id,
+        whitelist_info,
+        &[WHITELIST_SEED, config_info.key.as_ref(), wallet.as_ref()],
+    )?;
+    // an existing entry just gets its allocation replaced
+    if whitelist_info.data_is_empty() {
+        msg!("Create Whitelist Entry");
+        create_or_allocate_account_raw(
+            *program_id,
+            whitelist_info,
+            rent_info,
+            system_info,
+            authority_info,
+            WhitelistEntry::LEN,
+            &[WHITELIST_SEED, config_info.key.as_ref(), wallet.as_ref(), &[bump]],
+        )?;
+    } else {
+        WhitelistEntry::from_account_info(program_id, whitelist_info)?;
+    }
+    msg!("Whitelist {} for {}", wallet, allocation);
+    let entry = WhitelistEntry {
+        config: *config_info.key,
+        wallet,
+        remaining_mints: allocation,
+        bump,
+        version: STATE_VERSION,
+    };
+    entry.serialize(&mut *whitelist_info.data.borrow_mut())?;
+    Ok(())
+}
+
+pub fn process_remove_from_whitelist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    wallet: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let whitelist_info = next_account_info(account_info_iter)?;
+
+    assert_config_authority(program_id, config_info, authority_info)?;
+    assert_derivation(
+        program_id,
+        whitelist_info,
+        &[WHITELIST_SEED, config_info.key.as_ref(), wallet.as_ref()],
+    )?;
+    assert_owned_by(whitelist_info, program_id)?;
+
+    msg!("Remove Whitelist Entry {}", wallet);
+    whitelist_info.data.borrow_mut().fill(0);
+    let lamports = whitelist_info.lamports();
+    **whitelist_info.lamports.borrow_mut() = 0;
+    **authority_info.lamports.borrow_mut() = checked_add(authority_info.lamports(), lamports)?;
+    Ok(())
+}
+
+/// Takes `count` mints from the minter's whitelist allocation when the config
+/// has the whitelist enabled. The entry is derived from the config and the
+/// minter, so another wallet's entry is rejected.
+pub fn consume_whitelist(
+    program_id: &Pubkey,
+    config: &CollectionConfig,
+    config_info: &AccountInfo,
+    minter_info: &AccountInfo,
+    whitelist_info: Option<&AccountInfo>,
+    count: u64,
+) -> ProgramResult {
+    if !config.whitelist_enabled {
+        return Ok(());
+    }
+    let whitelist_info = whitelist_info.ok_or(AppError::NotWhitelisted)?;
+    assert_derivation(
+        program_id,
+        whitelist_info,
+        &[WHITELIST_SEED, config_info.key.as_ref(), minter_info.key.as_ref()],
+    )?;
+    if whitelist_info.owner != program_id || whitelist_info.data_is_empty() {
+        return Err(AppError::NotWhitelisted.into());
+    }
+    let mut entry = WhitelistEntry::from_account_info(program_id, whitelist_info)?;
+    entry.remaining_mints = entry
+        .remaining_mints
+        .checked_sub(count)
+        .ok_or(AppError::AllocationExhausted)?;
+    entry.serialize(&mut *whitelist_info.data.borrow_mut())?;
+    Ok(())
+}
+
+pub fn process_set_whitelist_enabled(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    enabled: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    let mut config = assert_config_authority(program_id, config_info, authority_info)?;
+    msg!("Set Whitelist Enabled {}", enabled);
+    config.whitelist_enabled = enabled;
+    config.save(config_info)
+}
@@ -0,0 +1,368 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_metadata::state::{MAX_NAME_LENGTH, MAX_URI_LENGTH};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::AppError,
+    instruction::CreatorArgs,
+    utils::{assert_owned_by, try_from_slice_unchecked},
+};
+
+pub const RECORD_SEED: &[u8] = b"record";
+pub const MINT_AUTHORITY_SEED: &[u8] = b"mint_authority";
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const WHITELIST_SEED: &[u8] = b"whitelist";
+pub const WALLET_COUNTER_SEED: &[u8] = b"minted";
+pub const CONFIG_LINES_SEED: &[u8] = b"lines";
+pub const SESSION_SEED: &[u8] = b"session";
+
+/// Highest state layout this build understands. Accounts written before the
+/// version byte existed read as 0.
+pub const STATE_VERSION: u8 = 1;
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct MintRecord {
+    pub mint: Pubkey,
+    pub minter: Pubkey,
+    /// config the NFT was minted under, which alone may freeze or thaw it
+    pub config: Pubkey,
+    /// slot the mint landed in
+    pub slot: u64,
+    /// unix timestamp of that slot
+    pub unix_timestamp: i64,
+    pub bump: u8,
+    pub version: u8,
+}
+
+impl MintRecord {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 1 + 1;
+
+    pub const VERSION_OFFSET: usize = Self::LEN - 1;
+
+    pub fn from_account_info(program_id: &Pubkey, account_info: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_owned_by(account_info, program_id)?;
+        load_versioned(&account_info.data.borrow(), Self::VERSION_OFFSET)
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct WhitelistEntry {
+    pub config: Pubkey,
+    pub wallet: Pubkey,
+    pub remaining_mints: u64,
+    pub bump: u8,
+    pub version: u8,
+}
+
+impl WhitelistEntry {
+    pub const LEN: usize = 32 + 32 + 8 + 1 + 1;
+
+    pub const VERSION_OFFSET: usize = Self::LEN - 1;
+
+    pub fn from_account_info(program_id: &Pubkey, account_info: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_owned_by(account_info, program_id)?;
+        load_versioned(&account_info.data.borrow(), Self::VERSION_OFFSET)
+    }
+}
+
+/// Mints made by one wallet under one config, only tracked while the config
+/// has a `max_per_wallet`.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct WalletCounter {
+    pub count: u8,
+    pub bump: u8,
+    pub version: u8,
+}
+
+impl WalletCounter {
+    pub const LEN: usize = 1 + 1 + 1;
+
+    pub const VERSION_OFFSET: usize = Self::LEN - 1;
+
+    pub fn from_account_info(program_id: &Pubkey, account_info: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_owned_by(account_info, program_id)?;
+        load_versioned(&account_info.data.borrow(), Self::VERSION_OFFSET)
+    }
+}
+
+/// A short-lived key allowed to sign Mint for `player`, derived from the
+/// player and the session key.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Session {
+    pub player: Pubkey,
+    pub session_key: Pubkey,
+    /// unix timestamp from which the session no longer mints
+    pub expiry: u64,
+    pub remaining_mints: u64,
+    pub bump: u8,
+    pub version: u8,
+}
+
+impl Session {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1 + 1;
+
+    pub const VERSION_OFFSET: usize = Self::LEN - 1;
+
+    pub fn from_account_info(program_id: &Pubkey, account_info: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_owned_by(account_info, program_id)?;
+        load_versioned(&account_info.data.borrow(), Self::VERSION_OFFSET)
+    }
+}
+
+/// Start of the config lines PDA, followed by `total` fixed-size lines.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct ConfigLinesHeader {
+    pub total: u32,
+    pub remaining: u32,
+    pub bump: u8,
+    pub version: u8,
+}
+
+impl ConfigLinesHeader {
+    pub const LEN: usize = 4 + 4 + 1 + 1;
+
+    pub const VERSION_OFFSET: usize = Self::LEN - 1;
+
+    /// Reads the header only, the lines after it are accessed in place.
+    pub fn from_account_info(program_id: &Pubkey, account_info: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_owned_by(account_info, program_id)?;
+        let data = account_info.data.borrow();
+        let header = data.get(..Self::LEN).ok_or(ProgramError::AccountDataTooSmall)?;
+        load_versioned(header, Self::VERSION_OFFSET)
+    }
+}
+
+/// One raw line: a consumed flag, then the name and uri null-padded to the
+/// metadata limits.
+pub struct ConfigLine;
+
+impl ConfigLine {
+    pub const NAME_OFFSET: usize = 1;
+    pub const URI_OFFSET: usize = Self::NAME_OFFSET + MAX_NAME_LENGTH;
+    pub const LEN: usize = Self::URI_OFFSET + MAX_URI_LENGTH;
+
+    /// Offset of line `index`, or the account size for `index` lines.
+    pub fn offset(index: u32) -> usize {
+        ConfigLinesHeader::LEN + index as usize * Self::LEN
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct CollectionConfig {
+    pub authority: Pubkey,
+    pub max_supply: u64,
+    pub minted: u64,
+    pub price_lamports: u64,
+    pub treasury: Pubkey,
+    pub bump: u8,
+    pub version: u8,
+    /// wallets allowed to mint besides the authority; empty means anyone can
+    pub delegates: Vec<Pubkey>,
+    /// when set, mints are paid in this token instead of SOL
+    pub payment_mint: Option<Pubkey>,
+    pub price_tokens: u64,
+    /// when set, replaces the uri of every mint with `{index}` filled in
+    pub uri_template: Option<String>,
+    /// mints need a WhitelistEntry for the signer while set
+    pub whitelist_enabled: bool,
+    /// unix timestamps bounding public minting, 0 means no bound on that side
+    pub mint_start: u64,
+    pub mint_end: u64,
+    /// mints allowed per signer, 0 means unlimited
+    pub max_per_wallet: u8,
+    /// rejects every mint, the authority's included, while set
+    pub is_paused: bool,
+    /// mints take their name and uri from a random config line
+    pub randomized: bool,
+    /// root of the MintWithProof allowlist, all zero when unset
+    pub merkle_root: [u8; 32],
+    /// collection every mint is verified into, set by InitializeCollection
+    pub collection_mint: Option<Pubkey>,
+    /// set for good by SealCollection, no admin change or mint after that
+    pub sealed: bool,
+    /// share of every SOL mint price sent to `fee_recipient`, in basis points
+    pub protocol_fee_bps: u16,
+    pub fee_recipient: Pubkey,
+    /// creators of mints that don't list their own, empty for the signer alone
+    pub default_creators: Vec<CreatorArgs>,
+}
+
+impl CollectionConfig {
+    /// Allocated size. Everything past the serialized fields stays zeroed, so
+    /// fields appended later read as zero/None/false on existing configs.
+    pub const LEN: usize = 1024;
+
+    /// Offset of `version`, right after the fixed-size fields that precede it.
+    pub const VERSION_OFFSET: usize = 32 + 8 + 8 + 8 + 32 + 1;
+
+    pub const MAX_DELEGATES: usize = 10;
+
+    pub fn can_mint(&self, signer: &Pubkey) -> bool {
+        self.delegates.is_empty() || self.authority == *signer || self.delegates.contains(signer)
+    }
+
+    pub fn from_account_info(program_id: &Pubkey, account_info: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_owned_by(account_info, program_id)?;
+        load_versioned(&account_info.data.borrow(), Self::VERSION_OFFSET)
+    }
+
+    /// Writes the config over a zeroed account, so a list or option that
+    /// shrank leaves no stale bytes for fields appended later. The writer is
+    /// a reborrow, writing through the account's own slice would advance it
+    /// and later loads in the same instruction would read past the config.
+    pub fn save(&self, account_info: &AccountInfo) -> ProgramResult {
+        let mut data = account_info.data.borrow_mut();
+        data.fill(0);
+        self.serialize(&mut &mut data[..])?;
+        Ok(())
+    }
+}
+
+/// Refuses accounts written by a newer program than this one.
+pub fn assert_state_version(version: u8) -> Result<(), ProgramError> {
+    if version > STATE_VERSION {
+        msg!("State version {} is newer than supported {}", version, STATE_VERSION);
+        return Err(AppError::StateFromNewerVersion.into());
+    }
+    Ok(())
+}
+
+/// Version byte of `data` at `version_offset`, 0 when the account is too
+/// short to hold one.
+pub fn state_version(data: &[u8], version_offset: usize) -> u8 {
+    data.get(version_offset).copied().unwrap_or(0)
+}
+
+/// Version byte of one of our state accounts, which are told apart by size.
+/// None for a size no state type has.
+pub fn account_state_version(data: &[u8]) -> Option<u8> {
+    let version_offset = match data.len() {
+        CollectionConfig::LEN => CollectionConfig::VERSION_OFFSET,
+        MintRecord::LEN => MintRecord::VERSION_OFFSET,
+        WhitelistEntry::LEN => WhitelistEntry::VERSION_OFFSET,
+        WalletCounter::LEN => WalletCounter::VERSION_OFFSET,
+        Session::LEN => Session::VERSION_OFFSET,
+        len if len >= ConfigLinesHeader::LEN
+            && (len - ConfigLinesHeader::LEN).is_multiple_of(ConfigLine::LEN) =>
+        {
+            ConfigLinesHeader::VERSION_OFFSET
+        }
+        _ => return None,
+    };
+    Some(state_version(data, version_offset))
+}
+
+/// Checks the version byte before deserializing, so a layout this build
+/// doesn't know is never misread.
+fn load_versioned<T: BorshDeserialize>(
+    data: &[u8],
+    version_offset: usize,
+) -> Result<T, ProgramError> {
+    assert_state_version(state_version(data, version_offset))?;
+    Ok(try_from_slice_unchecked(data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    type Load = fn(&Pubkey, &AccountInfo) -> ProgramResult;
+
+    /// Size, version offset and loader of every state account type.
+    fn state_types() -> [(usize, usize, Load); 6] {
+        [
+            (CollectionConfig::LEN, CollectionConfig::VERSION_OFFSET, |program_id, info| {
+                CollectionConfig::from_account_info(program_id, info).map(drop)
+            }),
+            (MintRecord::LEN, MintRecord::VERSION_OFFSET, |program_id, info| {
+                MintRecord::from_account_info(program_id, info).map(drop)
+            }),
+            (WhitelistEntry::LEN, WhitelistEntry::VERSION_OFFSET, |program_id, info| {
+                WhitelistEntry::from_account_info(program_id, info).map(drop)
+            }),
+            (WalletCounter::LEN, WalletCounter::VERSION_OFFSET, |program_id, info| {
+                WalletCounter::from_account_info(program_id, info).map(drop)
+            }),
+            (Session::LEN, Session::VERSION_OFFSET, |program_id, info| {
+                Session::from_account_info(program_id, info).map(drop)
+            }),
+            (ConfigLine::offset(2), ConfigLinesHeader::VERSION_OFFSET, |program_id, info| {
+                ConfigLinesHeader::from_account_info(program_id, info).map(drop)
+            }),
+        ]
+    }
+
+    /// A zeroed program-owned account of `len` bytes with a version byte.
+    fn versioned(len: usize, version_offset: usize, version: u8) -> TestAccount {
+        let mut data = vec![0; len];
+        data[version_offset] = version;
+        TestAccount::new(Pubkey::new_unique(), crate::id(), data)
+    }
+
+    #[test]
+    fn every_state_type_loads_the_supported_versions() {
+        setup();
+        for (len, version_offset, load) in state_types() {
+            for version in 0..=STATE_VERSION {
+                let mut account = versioned(len, version_offset, version);
+                assert_eq!(load(&crate::id(), &account.info()), Ok(()), "len {}", len);
+            }
+        }
+    }
+
+    #[test]
+    fn every_state_type_refuses_a_future_version() {
+        setup();
+        for (len, version_offset, load) in state_types() {
+            let mut account = versioned(len, version_offset, STATE_VERSION + 1);
+            assert_eq!(
+                load(&crate::id(), &account.info()),
+                Err(AppError::StateFromNewerVersion.into()),
+                "len {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn every_state_type_refuses_another_owner() {
+        setup();
+        for (len, version_offset, load) in state_types() {
+            let mut account = versioned(len, version_offset, STATE_VERSION);
+            account.owner = Pubkey::new_unique();
+            assert!(load(&crate::id(), &account.info()).is_err(), "len {}", len);
+        }
+    }
+
+    #[test]
+    fn state_version_is_found_by_account_size() {
+        for (len, version_offset, _) in state_types() {
+            let account = versioned(len, version_offset, 7);
+            assert_eq!(account_state_version(&account.data), Some(7), "len {}", len);
+        }
+        assert_eq!(account_state_version(&[1; 5]), None);
+    }
+
+    #[test]
+    fn config_reloads_after_repeated_saves() {
+        let mut config = test_config(Pubkey::new_unique());
+        let mut account = config_account(&config);
+        let info = account.info();
+        for minted in 1..=2 {
+            config.minted = minted;
+            config.save(&info).unwrap();
+            let loaded = CollectionConfig::from_account_info(&crate::id(), &info);
+            assert_eq!(loaded, Ok(config.clone()));
+        }
+    }
+}
@@ -0,0 +1,541 @@
+//! Fake accounts and syscall stubs for running processors off chain.
+//!
+//! CPIs are recorded instead of executed, so a test sets up every account in
+//! the state the callee would leave it in and checks the recorded calls.
+// helpers are shared by the test modules, not every one uses all of them
+#![allow(dead_code)]
+
+use std::{cell::RefCell, sync::Once};
+
+use borsh::BorshSerialize;
+use borsh::BorshDeserialize;
+use mpl_token_metadata::{
+    instruction::MetadataInstruction,
+    pda::{find_master_edition_account, find_metadata_account},
+    state::{Creator, Data, DataV2, Key, Metadata, MAX_METADATA_LEN},
+};
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_option::COption,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    system_program,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{
+    instruction::MintNftArgs,
+    state::{
+        CollectionConfig, MintRecord, CONFIG_SEED, MINT_AUTHORITY_SEED, RECORD_SEED, STATE_VERSION,
+    },
+};
+
+/// One CPI made by the code under test.
+#[derive(Clone, Debug)]
+pub struct Invocation {
+    pub instruction: Instruction,
+    /// number of PDA signer seed sets, 0 for a plain `invoke`
+    pub signers: usize,
+}
+
+thread_local! {
+    static INVOKED: RefCell<Vec<Invocation>> = const { RefCell::new(vec![]) };
+    static CLOCK: RefCell<Clock> = RefCell::new(Clock::default());
+    static RETURN_DATA: RefCell<Option<(Pubkey, Vec<u8>)>> = const { RefCell::new(None) };
+}
+
+struct TestStubs;
+
+impl SyscallStubs for TestStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        INVOKED.with(|invoked| {
+            invoked.borrow_mut().push(Invocation {
+                instruction: instruction.clone(),
+                signers: signers_seeds.len(),
+            })
+        });
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = CLOCK.with(|clock| clock.borrow().clone());
+        // Clock::get hands over a pointer to a Clock
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.with(|data| data.borrow().clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = Some((crate::id(), data.to_vec())));
+    }
+}
+
+/// Installs the stubs and clears what earlier tests on this thread recorded.
+/// The clock starts at slot 100, unix timestamp 1_000_000.
+pub fn setup() {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(TestStubs));
+    });
+    INVOKED.with(|invoked| invoked.borrow_mut().clear());
+    RETURN_DATA.with(|data| *data.borrow_mut() = None);
+    set_clock(100, 1_000_000);
+}
+
+pub fn set_clock(slot: u64, unix_timestamp: i64) {
+    CLOCK.with(|clock| {
+        *clock.borrow_mut() = Clock {
+            slot,
+            unix_timestamp,
+            ..Clock::default()
+        }
+    });
+}
+
+/// CPIs recorded since `setup`, oldest first.
+pub fn invoked() -> Vec<Invocation> {
+    INVOKED.with(|invoked| invoked.borrow().clone())
+}
+
+/// CPIs recorded since `setup` that went to `program_id`.
+pub fn invoked_program(program_id: &Pubkey) -> Vec<Invocation> {
+    invoked()
+        .into_iter()
+        .filter(|invocation| invocation.instruction.program_id == *program_id)
+        .collect()
+}
+
+pub fn return_data() -> Option<Vec<u8>> {
+    RETURN_DATA.with(|data| data.borrow().clone().map(|(_, data)| data))
+}
+
+/// Owned backing store for one `AccountInfo`.
+#[derive(Clone, Debug)]
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    /// A writable, rent-funded account.
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        TestAccount {
+            key,
+            owner,
+            lamports: 1_000_000_000,
+            data,
+            is_signer: false,
+            is_writable: true,
+            executable: false,
+        }
+    }
+
+    /// A system-owned wallet without data.
+    pub fn wallet(key: Pubkey) -> Self {
+        Self::new(key, system_program::id(), vec![])
+    }
+
+    /// An account that doesn't exist yet.
+    pub fn empty(key: Pubkey) -> Self {
+        TestAccount {
+            lamports: 0,
+            ..Self::wallet(key)
+        }
+    }
+
+    pub fn program(key: Pubkey) -> Self {
+        TestAccount {
+            is_writable: false,
+            executable: true,
+            ..Self::new(key, bpf_loader::id(), vec![])
+        }
+    }
+
+    /// A program-owned account holding `state`.
+    pub fn state<T: BorshSerialize>(key: Pubkey, owner: Pubkey, state: &T, len: usize) -> Self {
+        let mut data = state.try_to_vec().unwrap();
+        data.resize(len, 0);
+        Self::new(key, owner, data)
+    }
+
+    pub fn rent() -> Self {
+        let mut account = Self::new(
+            solana_program::sysvar::rent::id(),
+            solana_program::sysvar::id(),
+            vec![0; Rent::size_of()],
+        );
+        Rent::default()
+            .to_account_info(&mut account.info())
+            .unwrap();
+        account
+    }
+
+    /// A SlotHashes sysvar whose newest entry has `hash`.
+    pub fn slot_hashes(hash: [u8; 32]) -> Self {
+        let mut data = 1u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&99u64.to_le_bytes());
+        data.extend_from_slice(&hash);
+        Self::new(
+            solana_program::sysvar::slot_hashes::id(),
+            solana_program::sysvar::id(),
+            data,
+        )
+    }
+
+    /// An initialized token account of either token program.
+    pub fn token_account(
+        key: Pubkey,
+        token_program: &Pubkey,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> Self {
+        let data = if *token_program == spl_token_2022::id() {
+            let mut data = vec![0; spl_token_2022::state::Account::LEN];
+            spl_token_2022::state::Account {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                state: spl_token_2022::state::AccountState::Initialized,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        } else {
+            let mut data = vec![0; spl_token::state::Account::LEN];
+            spl_token::state::Account {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        };
+        Self::new(key, *token_program, data)
+    }
+
+    /// An initialized mint of either token program.
+    pub fn mint(key: Pubkey, token_program: &Pubkey, authority: &Pubkey, decimals: u8) -> Self {
+        let data = if *token_program == spl_token_2022::id() {
+            let mut data = vec![0; spl_token_2022::state::Mint::LEN];
+            spl_token_2022::state::Mint {
+                mint_authority: COption::Some(*authority),
+                decimals,
+                is_initialized: true,
+                freeze_authority: COption::Some(*authority),
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        } else {
+            let mut data = vec![0; spl_token::state::Mint::LEN];
+            spl_token::state::Mint {
+                mint_authority: COption::Some(*authority),
+                decimals,
+                is_initialized: true,
+                freeze_authority: COption::Some(*authority),
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        };
+        Self::new(key, *token_program, data)
+    }
+
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+pub fn infos(accounts: &mut [TestAccount]) -> Vec<AccountInfo<'_>> {
+    accounts.iter_mut().map(TestAccount::info).collect()
+}
+
+/// Key of a PDA of this program.
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &crate::id()).0
+}
+
+/// An open, free config of `authority` with room for ten mints.
+pub fn test_config(authority: Pubkey) -> CollectionConfig {
+    CollectionConfig {
+        authority,
+        max_supply: 10,
+        minted: 0,
+        price_lamports: 0,
+        treasury: Pubkey::new_unique(),
+        bump: 0,
+        version: STATE_VERSION,
+        delegates: vec![],
+        payment_mint: None,
+        price_tokens: 0,
+        uri_template: None,
+        whitelist_enabled: false,
+        mint_start: 0,
+        mint_end: 0,
+        max_per_wallet: 0,
+        is_paused: false,
+        randomized: false,
+        merkle_root: [0; 32],
+        collection_mint: None,
+        sealed: false,
+        protocol_fee_bps: 0,
+        fee_recipient: Pubkey::default(),
+        default_creators: vec![],
+    }
+}
+
+/// The config PDA of `config.authority` holding `config`.
+pub fn config_account(config: &CollectionConfig) -> TestAccount {
+    TestAccount::state(
+        pda(&[CONFIG_SEED, config.authority.as_ref()]),
+        crate::id(),
+        config,
+        CollectionConfig::LEN,
+    )
+}
+
+pub fn load_config(account: &TestAccount) -> CollectionConfig {
+    crate::utils::try_from_slice_unchecked(&account.data).unwrap()
+}
+
+/// Args of a plain 1/1 NFT of `expected_mint`, royalties to the signer.
+pub fn test_mint_args(expected_mint: Pubkey) -> MintNftArgs {
+    MintNftArgs {
+        uri: String::from("https://example.com/0.json"),
+        title: None,
+        symbol: None,
+        seller_fee_basis_points: 500,
+        creators: None,
+        mutable: true,
+        max_supply: Some(0),
+        decimals: 0,
+        amount: 1,
+        return_mint: false,
+        collection_mint: None,
+        strict: false,
+        sized_collection: false,
+        soulbound: false,
+        expected_mint,
+        recipient: None,
+        trait_options: vec![],
+        update_authority: None,
+    }
+}
+
+/// Mutable metadata of `mint` with the update authority as only creator.
+pub fn test_metadata(mint: Pubkey, update_authority: Pubkey) -> Metadata {
+    Metadata {
+        key: Key::MetadataV1,
+        update_authority,
+        mint,
+        data: Data {
+            name: String::from("Violent squirrel"),
+            symbol: String::from("SQRL"),
+            uri: String::from("https://example.com/0.json"),
+            seller_fee_basis_points: 500,
+            creators: Some(vec![Creator {
+                address: update_authority,
+                verified: true,
+                share: 100,
+            }]),
+        },
+        primary_sale_happened: false,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: None,
+        collection: None,
+        uses: None,
+        collection_details: None,
+    }
+}
+
+/// The metadata PDA of `metadata.mint` holding `metadata`.
+pub fn metadata_account(metadata: &Metadata) -> TestAccount {
+    TestAccount::state(
+        find_metadata_account(&metadata.mint).0,
+        mpl_token_metadata::id(),
+        metadata,
+        MAX_METADATA_LEN,
+    )
+}
+
+/// Fills an optional account slot with the "absent" sentinel.
+pub fn absent() -> TestAccount {
+    TestAccount::program(crate::id())
+}
+
+/// Record and config slots of an NFT this program never minted.
+pub fn unrecorded(mint: &Pubkey) -> [TestAccount; 2] {
+    [TestAccount::empty(pda(&[RECORD_SEED, mint.as_ref()])), absent()]
+}
+
+/// Record and config slots of an NFT minted under `config`.
+pub fn recorded(mint: &Pubkey, config: &CollectionConfig) -> [TestAccount; 2] {
+    let config = config_account(config);
+    let record = MintRecord {
+        mint: *mint,
+        minter: Pubkey::new_unique(),
+        config: config.key,
+        slot: 1,
+        unix_timestamp: 1,
+        bump: 0,
+        version: STATE_VERSION,
+    };
+    [
+        TestAccount::state(
+            pda(&[RECORD_SEED, mint.as_ref()]),
+            crate::id(),
+            &record,
+            MintRecord::LEN,
+        ),
+        config,
+    ]
+}
+
+/// Account positions of a Mint instruction.
+pub mod mint_slot {
+    pub const AUTHORITY: usize = 0;
+    pub const SIGNER: usize = 1;
+    pub const MINT: usize = 2;
+    pub const ATA: usize = 3;
+    pub const TOKEN_PROGRAM: usize = 4;
+    pub const METADATA: usize = 9;
+    pub const EDITION: usize = 10;
+    pub const RECORD: usize = 11;
+    pub const CONFIG: usize = 12;
+    pub const TREASURY: usize = 13;
+    pub const PAYMENT_MINT: usize = 14;
+    pub const PAYMENT_SOURCE: usize = 15;
+    pub const PAYMENT_DESTINATION: usize = 16;
+    pub const WHITELIST: usize = 17;
+    pub const COUNTER: usize = 18;
+    pub const LINES: usize = 19;
+    pub const SLOT_HASHES: usize = 20;
+    pub const RECIPIENT: usize = 21;
+    pub const COLLECTION_MINT: usize = 22;
+    pub const COLLECTION_METADATA: usize = 23;
+    pub const COLLECTION_EDITION: usize = 24;
+    pub const FEE_RECIPIENT: usize = 25;
+    pub const SESSION: usize = 26;
+}
+
+/// Associated token address of `wallet` for an spl-token `mint`.
+pub fn ata(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address(wallet, mint)
+}
+
+/// Accounts of a Mint by `signer` under `config`, with every optional slot
+/// absent. The NFT accounts are in the state the CPIs would leave them in.
+pub fn mint_accounts(config: &CollectionConfig, signer: Pubkey) -> Vec<TestAccount> {
+    let mut accounts = vec![
+        TestAccount::new(pda(&[MINT_AUTHORITY_SEED, crate::id().as_ref()]), crate::id(), vec![]),
+        TestAccount::wallet(signer).signer(),
+        TestAccount::empty(Pubkey::default()),
+        TestAccount::empty(Pubkey::default()),
+        TestAccount::program(spl_token::id()),
+        TestAccount::program(spl_associated_token_account::id()),
+        TestAccount::rent(),
+        TestAccount::program(system_program::id()),
+        TestAccount::program(mpl_token_metadata::id()),
+        TestAccount::empty(Pubkey::default()),
+        TestAccount::empty(Pubkey::default()),
+        TestAccount::empty(Pubkey::default()),
+        config_account(config),
+        TestAccount::wallet(config.treasury),
+    ];
+    accounts.extend((0..13).map(|_| absent()));
+    set_mint(&mut accounts, Pubkey::new_unique());
+    accounts
+}
+
+/// Points the NFT slots of Mint `accounts` at a fresh `mint` for the current
+/// recipient, so the same accounts can mint again.
+pub fn set_mint(accounts: &mut [TestAccount], mint: Pubkey) {
+    let recipient = mint_recipient(accounts);
+    let authority = accounts[mint_slot::AUTHORITY].key;
+    accounts[mint_slot::MINT] = TestAccount::mint(mint, &spl_token::id(), &authority, 0).signer();
+    accounts[mint_slot::ATA] =
+        TestAccount::token_account(ata(&recipient, &mint), &spl_token::id(), &mint, &recipient, 1);
+    accounts[mint_slot::METADATA] = TestAccount::empty(find_metadata_account(&mint).0);
+    accounts[mint_slot::EDITION] = TestAccount::empty(find_master_edition_account(&mint).0);
+    accounts[mint_slot::RECORD] = TestAccount::new(
+        pda(&[RECORD_SEED, mint.as_ref()]),
+        crate::id(),
+        vec![0; MintRecord::LEN],
+    );
+}
+
+/// Passes `recipient` in the recipient slot and moves the token account to it.
+pub fn set_mint_recipient(accounts: &mut [TestAccount], recipient: Pubkey) {
+    accounts[mint_slot::RECIPIENT] = TestAccount::wallet(recipient);
+    let mint = accounts[mint_slot::MINT].key;
+    set_mint(accounts, mint);
+}
+
+/// Wallet the Mint `accounts` create the token account for.
+pub fn mint_recipient(accounts: &[TestAccount]) -> Pubkey {
+    match &accounts[mint_slot::RECIPIENT] {
+        recipient if recipient.key == crate::id() => accounts[mint_slot::SIGNER].key,
+        recipient => recipient.key,
+    }
+}
+
+/// Decoded instruction of every recorded token metadata CPI.
+pub fn metadata_calls() -> Vec<MetadataInstruction> {
+    invoked_program(&mpl_token_metadata::id())
+        .iter()
+        .map(|call| MetadataInstruction::try_from_slice(&call.instruction.data).unwrap())
+        .collect()
+}
+
+/// Data of every recorded create_metadata_accounts_v3 CPI.
+pub fn created_metadata() -> Vec<DataV2> {
+    metadata_calls()
+        .into_iter()
+        .filter_map(|call| match call {
+            MetadataInstruction::CreateMetadataAccountV3(args) => Some(args.data),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn load_record(account: &TestAccount) -> MintRecord {
+    crate::utils::try_from_slice_unchecked(&account.data).unwrap()
+}
@@ -1,13 +1,20 @@
 use std::io::Error;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::Hasher;
 use borsh::BorshDeserialize;
-use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program::{invoke, invoke_signed}, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, system_instruction, sysvar::{clock::Clock, rent::Rent, Sysvar}};
+use solana_program::{account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, hash::hashv, instruction::Instruction, msg, program::{invoke, invoke_signed}, program_error::ProgramError, program_pack::{IsInitialized, Pack}, pubkey::Pubkey, system_instruction, sysvar::{clock::Clock, rent::Rent, slot_hashes, Sysvar}};
 
-use crate::error::AppError;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use spl_token::state::AccountState;
+use spl_token_2022::{
+    extension::{ExtensionType, StateWithExtensions},
+    state::AccountState as Token2022AccountState,
+};
 
-pub fn now_timestamp() -> u64 {
-    Clock::get().unwrap().unix_timestamp as u64
+use mpl_token_metadata::state::{MAX_CREATOR_LIMIT, MAX_URI_LENGTH};
+
+use crate::{error::AppError, hashing::short_hash64, instruction::CreatorArgs};
+
+pub fn now_timestamp() -> Result<u64, ProgramError> {
+    Ok(Clock::get()?.unix_timestamp as u64)
 }
 
 pub fn assert_eq_pubkey(account_info: &AccountInfo, account: &Pubkey) -> ProgramResult {
@@ -18,6 +25,15 @@ pub fn assert_eq_pubkey(account_info: &AccountInfo, account: &Pubkey) -> Program
     }
 }
 
+/// Metadata CPIs take their program id from this account, so it must be the
+/// real token metadata program.
+pub fn assert_metadata_program(metadata_program_info: &AccountInfo) -> ProgramResult {
+    if *metadata_program_info.key != mpl_token_metadata::id() {
+        return Err(AppError::InvalidMetadataProgram.into());
+    }
+    Ok(())
+}
+
 pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
     if account.owner != owner {
         Err(AppError::InvalidOwner.into())
@@ -26,12 +42,87 @@ pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
     }
 }
 
+/// Unpacks a token account of `token_program` and checks it belongs to `owner`,
+/// failing with InvalidOwner otherwise. The token program picks the account
+/// layout, spl-token or Token-2022, and the unpacked account is handed back
+/// so callers don't unpack it a second time for its amount or state.
+pub fn assert_token_account_owner(
+    token_program: &TokenProgram,
+    token_account_info: &AccountInfo,
+    owner: &Pubkey,
+) -> Result<spl_token::state::Account, ProgramError> {
+    let token_account = token_program.unpack_account(token_account_info)?;
+    if token_account.owner != *owner {
+        return Err(AppError::InvalidOwner.into());
+    }
+    Ok(token_account)
+}
+
+/// Unpacks a token account of `token_program` and checks it holds `mint`,
+/// failing with InvalidEqPubkey otherwise.
+pub fn assert_token_account_mint(
+    token_program: &TokenProgram,
+    token_account_info: &AccountInfo,
+    mint: &Pubkey,
+) -> Result<spl_token::state::Account, ProgramError> {
+    let token_account = token_program.unpack_account(token_account_info)?;
+    if token_account.mint != *mint {
+        return Err(AppError::InvalidEqPubkey.into());
+    }
+    Ok(token_account)
+}
+
+/// Unpacks a token account of `token_program` once and checks both its owner
+/// and its mint.
+pub fn assert_token_account(
+    token_program: &TokenProgram,
+    token_account_info: &AccountInfo,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<spl_token::state::Account, ProgramError> {
+    let token_account = assert_token_account_owner(token_program, token_account_info, owner)?;
+    if token_account.mint != *mint {
+        return Err(AppError::InvalidEqPubkey.into());
+    }
+    Ok(token_account)
+}
+
+pub fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> ProgramResult {
+    if !rent.is_exempt(account_info.lamports(), account_info.data_len()) {
+        Err(AppError::NotRentExempt.into())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn assert_initialized<T: Pack + IsInitialized>(
+    account_info: &AccountInfo,
+) -> Result<T, ProgramError> {
+    let account: T = T::unpack_unchecked(&account_info.data.borrow())?;
+    if !account.is_initialized() {
+        Err(AppError::Uninitialized.into())
+    } else {
+        Ok(account)
+    }
+}
+
+pub fn assert_uninitialized<T: Pack + IsInitialized>(
+    account_info: &AccountInfo,
+) -> Result<T, ProgramError> {
+    let account: T = T::unpack_unchecked(&account_info.data.borrow())?;
+    if account.is_initialized() {
+        Err(AppError::AlreadyInitialized.into())
+    } else {
+        Ok(account)
+    }
+}
+
 pub fn assert_derivation(
     program_id: &Pubkey,
     account: &AccountInfo,
     path: &[&[u8]],
 ) -> Result<u8, ProgramError> {
-    let (key, bump) = Pubkey::find_program_address(&path, program_id);
+    let (key, bump) = Pubkey::find_program_address(path, program_id);
     if key != *account.key {
         return Err(AppError::InvalidDerivedKey.into());
     }
@@ -46,25 +137,369 @@ pub fn assert_signer(account_info: &AccountInfo) -> ProgramResult {
     }
 }
 
+/// Checks a creator list wherever one enters the program. Only the signer can be
+/// verified at creation, so unique addresses also mean at most one verified entry.
+pub fn validate_creators(creators: &[CreatorArgs]) -> ProgramResult {
+    if creators.len() > MAX_CREATOR_LIMIT {
+        return Err(AppError::TooManyCreators.into());
+    }
+    for (index, creator) in creators.iter().enumerate() {
+        if creators[..index].iter().any(|other| other.address == creator.address) {
+            return Err(AppError::DuplicateCreator.into());
+        }
+        if creator.share == 0 && !creator.provenance_only {
+            return Err(AppError::ZeroShareCreator.into());
+        }
+    }
+    let total: u16 = creators.iter().map(|creator| creator.share as u16).sum();
+    if total != 100 {
+        return Err(AppError::InvalidCreatorShares.into());
+    }
+    Ok(())
+}
+
+pub const URI_INDEX_PLACEHOLDER: &str = "{index}";
+pub const URI_LOCALE_PLACEHOLDER: &str = "{locale}";
+pub const URI_TRAITS_PLACEHOLDER: &str = "{traits}";
+
+/// Checks a config uri template, which may only contain the `{index}`,
+/// `{locale}` and `{traits}` placeholders and no other braces.
+pub fn validate_uri_template(template: &str) -> ProgramResult {
+    if template.is_empty() {
+        return Err(AppError::InvalidUri.into());
+    }
+    if template.len() > MAX_URI_LENGTH {
+        return Err(AppError::UriTooLong.into());
+    }
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        let end = match rest[start..].strip_prefix('{') {
+            Some(tail) => start + 1 + tail.find('}').ok_or(AppError::InvalidUriTemplate)?,
+            None => return Err(AppError::InvalidUriTemplate.into()),
+        };
+        let placeholder = &rest[start..=end];
+        if ![URI_INDEX_PLACEHOLDER, URI_LOCALE_PLACEHOLDER, URI_TRAITS_PLACEHOLDER]
+            .contains(&placeholder)
+        {
+            return Err(AppError::InvalidUriTemplate.into());
+        }
+        rest = &rest[end + 1..];
+    }
+    Ok(())
+}
+
+/// Fills in a uri template. On chain only the index is known, so `{locale}`
+/// stays literal there; clients pass the locale to get a fetchable uri.
+pub fn resolve_uri(template: &str, index: u64, locale: Option<&str>) -> String {
+    let uri = template.replace(URI_INDEX_PLACEHOLDER, &index.to_string());
+    match locale {
+        Some(locale) => uri.replace(URI_LOCALE_PLACEHOLDER, locale),
+        None => uri,
+    }
+}
+
+/// Overflow-checked u64 math for lamports, token amounts and counters.
+pub fn checked_add(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_add(b).ok_or_else(|| AppError::NumericalOverflow.into())
+}
+
+pub fn checked_sub(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_sub(b).ok_or_else(|| AppError::NumericalOverflow.into())
+}
+
+pub fn checked_mul(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_mul(b).ok_or_else(|| AppError::NumericalOverflow.into())
+}
+
+pub fn checked_div(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_div(b).ok_or_else(|| AppError::NumericalOverflow.into())
+}
+
+/// Splits `total` into `(creator_share, protocol_share)`, the protocol share
+/// rounded down so the two always add up to `total`.
+pub fn calculate_split(total: u64, fee_bps: u16) -> Result<(u64, u64), ProgramError> {
+    if fee_bps > 10_000 {
+        return Err(AppError::ArithmeticOverflow.into());
+    }
+    // at most total, the u128 product can't overflow
+    let protocol_share = (total as u128 * fee_bps as u128 / 10_000) as u64;
+    Ok((checked_sub(total, protocol_share)?, protocol_share))
+}
+
+/// Walks a merkle proof from `leaf` up to `root`. Each pair is hashed in sorted
+/// order, so proofs don't need to carry left/right positions: a parent is
+/// `sha256(min(a, b) || max(a, b))`, comparing the 32 bytes lexicographically.
+pub fn verify_merkle_proof(leaf: [u8; 32], proof: &[[u8; 32]], root: &[u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+    computed == *root
+}
+
+/// Takes the next account slot, treating `program_id` as the "absent" sentinel
+/// so that several optional accounts can be passed in the same instruction.
+/// The builders in `instruction` fill absent slots with the same id.
+pub fn optional_account<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    program_id: &Pubkey,
+    iter: &mut I,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    let account_info = next_account_info(iter)?;
+    if account_info.key == program_id {
+        Ok(None)
+    } else {
+        Ok(Some(account_info))
+    }
+}
+
+/// Newest entry of the SlotHashes sysvar. The full sysvar is too large to
+/// deserialize on chain, so only the first entry is read.
+fn recent_slot_hash(slot_hashes_info: &AccountInfo) -> Result<[u8; 32], ProgramError> {
+    if *slot_hashes_info.key != slot_hashes::id() {
+        return Err(AppError::InvalidSlotHashesSysvar.into());
+    }
+    let data = slot_hashes_info.data.borrow();
+    // u64 entry count, then (slot: u64, hash: [u8; 32]) entries, newest first
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(data.get(16..48).ok_or(AppError::InvalidSlotHashesSysvar)?);
+    Ok(hash)
+}
+
+/// Mixes the most recent slot hash and the clock timestamp with a caller
+/// nonce such as a mint pubkey, so callers in the same slot still differ.
+///
+/// This is pseudo-randomness, not a secure source: the slot leader knows the
+/// inputs in advance and a client can simulate before sending, so it must not
+/// back fairness claims for high-value draws.
+pub fn get_random_u64(
+    slot_hashes_info: &AccountInfo,
+    seed_bytes: &[u8],
+) -> Result<u64, ProgramError> {
+    let recent_hash = recent_slot_hash(slot_hashes_info)?;
+    let clock = Clock::get()?;
+    Ok(short_hash64(&[
+        &recent_hash,
+        &clock.unix_timestamp.to_le_bytes(),
+        seed_bytes,
+    ]))
+}
+
+/// Uniform value in `0..max` drawn like `get_random_u64`.
+pub fn get_random_range(
+    slot_hashes_info: &AccountInfo,
+    seed_bytes: &[u8],
+    max: u64,
+) -> Result<u64, ProgramError> {
+    let recent_hash = recent_slot_hash(slot_hashes_info)?;
+    let timestamp = Clock::get()?.unix_timestamp.to_le_bytes();
+    draw_range(&[&recent_hash, &timestamp, seed_bytes], max)
+}
+
+/// Hashes `inputs` into `0..max`. Draws past the largest multiple of `max`
+/// would favour small results, so they are rejected and redrawn with a round
+/// byte appended.
+fn draw_range(inputs: &[&[u8]], max: u64) -> Result<u64, ProgramError> {
+    if max == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let limit = u64::MAX - u64::MAX % max;
+    let mut random = short_hash64(inputs);
+    // each redraw is rejected with probability below 1/2
+    for round in 1..=u8::MAX {
+        if random < limit {
+            break;
+        }
+        let round = [round];
+        let mut redraw = inputs.to_vec();
+        redraw.push(&round);
+        random = short_hash64(&redraw);
+    }
+    Ok(random % max)
+}
+
+#[deprecated(note = "use get_random_u64 with a nonce such as the mint pubkey")]
 pub fn get_random(seed: u8) -> Result<u64, ProgramError> {
     let clock = Clock::get()?;
-    let mut hasher = DefaultHasher::new();
-    hasher.write_u8(seed);
-    hasher.write_u64(clock.slot);
-    hasher.write_i64(clock.unix_timestamp);
-    let mut random_value: [u8; 8] = [0u8; 8];
-    random_value.copy_from_slice(&hasher.finish().to_le_bytes()[..8]);
-    Ok(u64::from_le_bytes(random_value))
+    Ok(short_hash64(&[
+        &[seed],
+        &clock.slot.to_le_bytes(),
+        &clock.unix_timestamp.to_le_bytes(),
+    ]))
 }
 
+/// Uniform value in `0..divisor`, which has to fit a u8 result.
+#[deprecated(note = "use get_random_range with a nonce such as the mint pubkey")]
 pub fn get_random_u8(seed: u8, divisor: u64) -> Result<u8, ProgramError> {
-    let random = get_random(seed)?;
-    Ok((random % divisor) as u8)
+    if divisor > 256 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let clock = Clock::get()?;
+    let random = draw_range(
+        &[&[seed], &clock.slot.to_le_bytes(), &clock.unix_timestamp.to_le_bytes()],
+        divisor,
+    )?;
+    Ok(random as u8)
+}
+
+/// Uses `invoke_signed` when PDA seeds are given and a plain `invoke` otherwise.
+#[inline(always)]
+pub fn invoke_maybe_signed(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signer_seeds: Option<&[&[u8]]>,
+) -> ProgramResult {
+    match signer_seeds {
+        Some(seeds) => invoke_signed(instruction, account_infos, &[seeds]),
+        None => invoke(instruction, account_infos),
+    }
+}
+
+/// Token program a helper builds its instructions against.
+#[derive(Clone)]
+pub enum TokenProgram<'a> {
+    Legacy(AccountInfo<'a>),
+    Token2022(AccountInfo<'a>),
+}
+
+impl<'a> TokenProgram<'a> {
+    pub fn from_account_info(account_info: &AccountInfo<'a>) -> Result<Self, ProgramError> {
+        if *account_info.key == spl_token::id() {
+            Ok(TokenProgram::Legacy(account_info.clone()))
+        } else if *account_info.key == spl_token_2022::id() {
+            Ok(TokenProgram::Token2022(account_info.clone()))
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+
+    pub fn account_info(&self) -> &AccountInfo<'a> {
+        match self {
+            TokenProgram::Legacy(account_info) | TokenProgram::Token2022(account_info) => {
+                account_info
+            }
+        }
+    }
+
+    pub fn id(&self) -> &Pubkey {
+        self.account_info().key
+    }
+
+    /// Size of a mint account without extensions.
+    pub fn mint_len(&self) -> usize {
+        match self {
+            TokenProgram::Legacy(_) => spl_token::state::Mint::LEN,
+            TokenProgram::Token2022(_) => {
+                ExtensionType::get_account_len::<spl_token_2022::state::Mint>(&[])
+            }
+        }
+    }
+
+    /// Associated token address of `wallet` for `mint`, which includes the
+    /// token program in its seeds.
+    pub fn associated_token_address(&self, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[wallet.as_ref(), self.id().as_ref(), mint.as_ref()],
+            &spl_associated_token_account::id(),
+        )
+        .0
+    }
+
+    pub fn create_associated_token_account(
+        &self,
+        funder: &Pubkey,
+        wallet: &Pubkey,
+        mint: &Pubkey,
+    ) -> Instruction {
+        let mut instruction = create_associated_token_account(funder, wallet, mint);
+        // the builder of this spl-associated-token-account version assumes spl-token
+        instruction.accounts[1].pubkey = self.associated_token_address(wallet, mint);
+        instruction.accounts[5].pubkey = *self.id();
+        instruction
+    }
+
+    /// Unpacks a token account of this program. Token-2022 accounts are read
+    /// past their extensions and returned in the spl-token layout, which
+    /// their base state shares.
+    pub fn unpack_account(
+        &self,
+        account_info: &AccountInfo,
+    ) -> Result<spl_token::state::Account, ProgramError> {
+        assert_owned_by(account_info, self.id())?;
+        let data = account_info.data.borrow();
+        match self {
+            TokenProgram::Legacy(_) => spl_token::state::Account::unpack(&data)
+                .map_err(|_| AppError::InvalidTokenAccount.into()),
+            TokenProgram::Token2022(_) => {
+                let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+                    .map_err(|_| AppError::InvalidTokenAccount)?
+                    .base;
+                let state = match account.state {
+                    Token2022AccountState::Uninitialized => AccountState::Uninitialized,
+                    Token2022AccountState::Initialized => AccountState::Initialized,
+                    Token2022AccountState::Frozen => AccountState::Frozen,
+                };
+                Ok(spl_token::state::Account {
+                    mint: account.mint,
+                    owner: account.owner,
+                    amount: account.amount,
+                    delegate: account.delegate,
+                    state,
+                    is_native: account.is_native,
+                    delegated_amount: account.delegated_amount,
+                    close_authority: account.close_authority,
+                })
+            }
+        }
+    }
+
+    /// Mint and owner of a token account of this program.
+    pub fn account_mint_and_owner(
+        &self,
+        account_info: &AccountInfo,
+    ) -> Result<(Pubkey, Pubkey), ProgramError> {
+        let account = self.unpack_account(account_info)?;
+        Ok((account.mint, account.owner))
+    }
+
+    pub fn mint_decimals(&self, mint_info: &AccountInfo) -> Result<u8, ProgramError> {
+        let data = mint_info.data.borrow();
+        match self {
+            TokenProgram::Legacy(_) => Ok(spl_token::state::Mint::unpack(&data)?.decimals),
+            TokenProgram::Token2022(_) => {
+                Ok(StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?
+                    .base
+                    .decimals)
+            }
+        }
+    }
+
+    /// Token account size for `mint_info`, including the extensions a token-2022
+    /// mint requires on its accounts.
+    pub fn account_len(&self, mint_info: &AccountInfo) -> Result<usize, ProgramError> {
+        match self {
+            TokenProgram::Legacy(_) => Ok(spl_token::state::Account::LEN),
+            TokenProgram::Token2022(_) => {
+                let data = mint_info.data.borrow();
+                let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+                let account_extensions = ExtensionType::get_required_init_account_extensions(
+                    &mint.get_extension_types()?,
+                );
+                Ok(ExtensionType::get_account_len::<spl_token_2022::state::Account>(
+                    &account_extensions,
+                ))
+            }
+        }
+    }
 }
 
 pub struct TokenTransferParams<'a: 'b, 'b> {
     /// source
     pub source: AccountInfo<'a>,
+    /// mint
+    pub mint: AccountInfo<'a>,
     /// destination
     pub destination: AccountInfo<'a>,
     /// amount
@@ -74,29 +509,46 @@ pub struct TokenTransferParams<'a: 'b, 'b> {
     /// authority_signer_seeds
     pub authority_signer_seeds: &'b [&'b [u8]],
     /// token_program
-    pub token_program: AccountInfo<'a>,
+    pub token_program: TokenProgram<'a>,
 }
 
 #[inline(always)]
 pub fn spl_token_transfer<'a>(
-    token_program: AccountInfo<'a>,
+    token_program: &TokenProgram<'a>,
     source: AccountInfo<'a>,
+    mint: AccountInfo<'a>,
     destination: AccountInfo<'a>,
     authority: AccountInfo<'a>,
     amount: u64,
     signer_seeds: &[&[u8]],
 ) -> Result<(), ProgramError> {
-    invoke_signed(
-        &spl_token::instruction::transfer(
-            token_program.key,
+    let decimals = token_program.mint_decimals(&mint)?;
+    let instruction = match token_program {
+        TokenProgram::Legacy(_) => spl_token::instruction::transfer_checked(
+            token_program.id(),
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        TokenProgram::Token2022(_) => spl_token_2022::instruction::transfer_checked(
+            token_program.id(),
             source.key,
+            mint.key,
             destination.key,
             authority.key,
             &[],
             amount,
+            decimals,
         )?,
-        &[source, destination, authority, token_program],
-        &[&signer_seeds],
+    };
+    invoke_signed(
+        &instruction,
+        &[source, mint, destination, authority, token_program.account_info().clone()],
+        &[signer_seeds],
     )
 }
 
@@ -119,7 +571,7 @@ pub fn create_or_allocate_account_raw<'a>(
     if required_lamports > 0 {
         msg!("Transfer {} lamports to the new account", required_lamports);
         invoke(
-            &system_instruction::transfer(&payer_info.key, new_account_info.key, required_lamports),
+            &system_instruction::transfer(payer_info.key, new_account_info.key, required_lamports),
             &[
                 payer_info.clone(),
                 new_account_info.clone(),
@@ -129,17 +581,18 @@ pub fn create_or_allocate_account_raw<'a>(
     }
 
     msg!("Allocate space for the account");
+    let space = size.try_into().map_err(|_| AppError::NumericalOverflow)?;
     invoke_signed(
-        &system_instruction::allocate(new_account_info.key, size.try_into().unwrap()),
+        &system_instruction::allocate(new_account_info.key, space),
         &[new_account_info.clone(), system_program_info.clone()],
-        &[&signer_seeds],
+        &[signer_seeds],
     )?;
 
     msg!("Assign the account to the owning program");
     invoke_signed(
         &system_instruction::assign(new_account_info.key, &program_id),
         &[new_account_info.clone(), system_program_info.clone()],
-        &[&signer_seeds],
+        &[signer_seeds],
     )?;
     msg!("Completed assignation!");
 
@@ -148,8 +601,9 @@ pub fn create_or_allocate_account_raw<'a>(
 
 
 #[inline(always)]
+#[allow(clippy::too_many_arguments)]
 pub fn spl_token_create_account<'a>(
-    token_program: &AccountInfo<'a>,
+    token_program: &TokenProgram<'a>,
     payer_info: &AccountInfo<'a>,
     mint_info: &AccountInfo<'a>,
     new_account: &AccountInfo<'a>,
@@ -158,8 +612,8 @@ pub fn spl_token_create_account<'a>(
     initialize_account_seeds: &[&[u8]], // when account is not a pda, is null
     rent_info: &AccountInfo<'a>,
 ) -> ProgramResult {
-    let size = spl_token::state::Account::LEN;
-    let rent = &Rent::from_account_info(&rent_info)?;
+    let size = token_program.account_len(mint_info)?;
+    let rent = &Rent::from_account_info(rent_info)?;
     let required_lamports = rent.minimum_balance(size);
 
     msg!("spl_token_create_account create");
@@ -169,17 +623,31 @@ pub fn spl_token_create_account<'a>(
             new_account.key,
             required_lamports,
             size as u64,
-            token_program.key,
+            token_program.id(),
         ),
         &[payer_info.clone(), new_account.clone()],
         &[create_account_seeds],
     )?;
 
     msg!("spl_token_create_account initialize");
+    let instruction = match token_program {
+        TokenProgram::Legacy(_) => spl_token::instruction::initialize_account(
+            token_program.id(),
+            new_account.key,
+            mint_info.key,
+            authority.key,
+        )?,
+        TokenProgram::Token2022(_) => spl_token_2022::instruction::initialize_account(
+            token_program.id(),
+            new_account.key,
+            mint_info.key,
+            authority.key,
+        )?,
+    };
     invoke_signed(
-        &spl_token::instruction::initialize_account(token_program.key, new_account.key, mint_info.key, authority.key)?,
+        &instruction,
         &[
-            token_program.clone(),
+            token_program.account_info().clone(),
             new_account.clone(),
             mint_info.clone(),
             authority.clone(),
@@ -197,3 +665,438 @@ pub fn try_from_slice_unchecked<T: BorshDeserialize>(data: &[u8]) -> Result<T, E
     let result = T::deserialize(&mut data_mut)?;
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn checked_math_computes_in_range() {
+        assert_eq!(checked_add(u64::MAX - 1, 1), Ok(u64::MAX));
+        assert_eq!(checked_sub(1, 1), Ok(0));
+        assert_eq!(checked_mul(u64::MAX, 1), Ok(u64::MAX));
+        assert_eq!(checked_div(u64::MAX, 2), Ok(u64::MAX / 2));
+    }
+
+    #[test]
+    fn checked_math_reports_overflow() {
+        let overflow = Err(AppError::NumericalOverflow.into());
+        assert_eq!(checked_add(u64::MAX, 1), overflow);
+        assert_eq!(checked_sub(0, 1), overflow);
+        assert_eq!(checked_mul(u64::MAX, 2), overflow);
+        assert_eq!(checked_div(1, 0), overflow);
+    }
+
+    fn creator(share: u8) -> CreatorArgs {
+        CreatorArgs { address: Pubkey::new_unique(), share, provenance_only: false }
+    }
+
+    #[test]
+    fn validate_creators_accepts_a_valid_list() {
+        let provenance = CreatorArgs { share: 0, provenance_only: true, ..creator(0) };
+        assert_eq!(validate_creators(&[creator(60), creator(40), provenance]), Ok(()));
+    }
+
+    #[test]
+    fn validate_creators_rejects_too_many_creators() {
+        let creators: Vec<_> = (0..6).map(|_| creator(0)).collect();
+        assert_eq!(validate_creators(&creators), Err(AppError::TooManyCreators.into()));
+    }
+
+    #[test]
+    fn validate_creators_rejects_a_duplicate_creator() {
+        let first = creator(50);
+        let creators = [first.clone(), first];
+        assert_eq!(validate_creators(&creators), Err(AppError::DuplicateCreator.into()));
+    }
+
+    #[test]
+    fn validate_creators_rejects_shares_not_adding_up_to_100() {
+        assert_eq!(
+            validate_creators(&[creator(60), creator(30)]),
+            Err(AppError::InvalidCreatorShares.into())
+        );
+        assert_eq!(
+            validate_creators(&[creator(60), creator(50)]),
+            Err(AppError::InvalidCreatorShares.into())
+        );
+    }
+
+    #[test]
+    fn validate_creators_rejects_a_zero_share_unless_provenance_only() {
+        assert_eq!(
+            validate_creators(&[creator(100), creator(0)]),
+            Err(AppError::ZeroShareCreator.into())
+        );
+    }
+
+    #[test]
+    fn validate_uri_template_accepts_known_placeholders() {
+        for template in [
+            "https://example.com/0.json",
+            "https://example.com/{index}.json",
+            "https://example.com/{locale}/{index}-{traits}.json",
+            "{index}{index}",
+        ] {
+            assert_eq!(validate_uri_template(template), Ok(()), "{}", template);
+        }
+    }
+
+    #[test]
+    fn validate_uri_template_rejects_malformed_templates() {
+        for template in [
+            "https://example.com/{id}.json",
+            "https://example.com/{INDEX}.json",
+            "https://example.com/{}.json",
+            "https://example.com/{index.json",
+            "https://example.com/index}.json",
+            "https://example.com/{{index}}.json",
+            "https://example.com/{in{index}.json",
+            "{",
+        ] {
+            assert_eq!(
+                validate_uri_template(template),
+                Err(AppError::InvalidUriTemplate.into()),
+                "{}",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn validate_uri_template_rejects_an_empty_or_too_long_template() {
+        assert_eq!(validate_uri_template(""), Err(AppError::InvalidUri.into()));
+        let template = format!("https://example.com/{}", "a".repeat(MAX_URI_LENGTH));
+        assert_eq!(validate_uri_template(&template), Err(AppError::UriTooLong.into()));
+    }
+
+    #[test]
+    fn resolve_uri_fills_the_index_and_the_locale() {
+        let template = "https://example.com/{locale}/{index}.json";
+        assert_eq!(resolve_uri(template, 7, Some("de")), "https://example.com/de/7.json");
+        assert_eq!(resolve_uri(template, 7, None), "https://example.com/{locale}/7.json");
+    }
+
+    #[test]
+    fn invoke_maybe_signed_signs_with_the_given_seeds() {
+        setup();
+        let (mut from, mut to) = (
+            TestAccount::wallet(Pubkey::new_unique()),
+            TestAccount::wallet(Pubkey::new_unique()),
+        );
+        let instruction = system_instruction::transfer(&from.key, &to.key, 1);
+        let seeds: &[&[u8]] = &[b"seed", &[255]];
+        invoke_maybe_signed(&instruction, &[from.info(), to.info()], Some(seeds)).unwrap();
+
+        let calls = invoked();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].signers, 1);
+        assert_eq!(calls[0].instruction, instruction);
+    }
+
+    #[test]
+    fn invoke_maybe_signed_invokes_unsigned_without_seeds() {
+        setup();
+        let (mut from, mut to) = (
+            TestAccount::wallet(Pubkey::new_unique()).signer(),
+            TestAccount::wallet(Pubkey::new_unique()),
+        );
+        let instruction = system_instruction::transfer(&from.key, &to.key, 1);
+        invoke_maybe_signed(&instruction, &[from.info(), to.info()], None).unwrap();
+
+        let calls = invoked();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].signers, 0);
+    }
+
+    #[test]
+    fn calculate_split_parts_add_up_to_the_total() {
+        for total in [0, 1, 9_999, 10_001, 123_456_789, u64::MAX] {
+            for fee_bps in [0, 1, 250, 3_333, 9_999, 10_000] {
+                let (creator_share, protocol_share) = calculate_split(total, fee_bps).unwrap();
+                assert_eq!(creator_share + protocol_share, total);
+            }
+        }
+        assert_eq!(calculate_split(1_000, 250), Ok((975, 25)));
+        assert_eq!(calculate_split(u64::MAX, 10_000), Ok((0, u64::MAX)));
+    }
+
+    #[test]
+    fn calculate_split_rejects_bps_above_10000() {
+        assert_eq!(calculate_split(1_000, 10_001), Err(AppError::ArithmeticOverflow.into()));
+    }
+
+    fn token_programs() -> [Pubkey; 2] {
+        [spl_token::id(), spl_token_2022::id()]
+    }
+
+    #[test]
+    fn assert_signer_requires_a_signature() {
+        let mut account = TestAccount::wallet(Pubkey::new_unique());
+        assert_eq!(assert_signer(&account.info()), Err(ProgramError::MissingRequiredSignature));
+        let mut account = account.signer();
+        assert_eq!(assert_signer(&account.info()), Ok(()));
+    }
+
+    #[test]
+    fn assert_owned_by_checks_the_owner() {
+        let owner = Pubkey::new_unique();
+        let mut account = TestAccount::new(Pubkey::new_unique(), owner, vec![]);
+        assert_eq!(assert_owned_by(&account.info(), &owner), Ok(()));
+        assert_eq!(
+            assert_owned_by(&account.info(), &Pubkey::new_unique()),
+            Err(AppError::InvalidOwner.into())
+        );
+    }
+
+    #[test]
+    fn assert_eq_pubkey_checks_the_key() {
+        let mut account = TestAccount::wallet(Pubkey::new_unique());
+        let key = account.key;
+        assert_eq!(assert_eq_pubkey(&account.info(), &key), Ok(()));
+        assert_eq!(
+            assert_eq_pubkey(&account.info(), &Pubkey::new_unique()),
+            Err(AppError::InvalidEqPubkey.into())
+        );
+    }
+
+    #[test]
+    fn assert_metadata_program_only_accepts_token_metadata() {
+        let mut metadata_program = TestAccount::program(mpl_token_metadata::id());
+        assert_eq!(assert_metadata_program(&metadata_program.info()), Ok(()));
+        let mut impostor = TestAccount::program(Pubkey::new_unique());
+        assert_eq!(
+            assert_metadata_program(&impostor.info()),
+            Err(AppError::InvalidMetadataProgram.into())
+        );
+    }
+
+    #[test]
+    fn assert_derivation_returns_the_bump_of_the_right_address() {
+        let seeds: &[&[u8]] = &[b"record", &[7; 32]];
+        let (key, bump) = Pubkey::find_program_address(seeds, &crate::id());
+        let mut account = TestAccount::wallet(key);
+        assert_eq!(assert_derivation(&crate::id(), &account.info(), seeds), Ok(bump));
+
+        let other_seeds: &[&[u8]] = &[b"record", &[8; 32]];
+        assert_eq!(
+            assert_derivation(&crate::id(), &account.info(), other_seeds),
+            Err(AppError::InvalidDerivedKey.into())
+        );
+        assert_eq!(
+            assert_derivation(&Pubkey::new_unique(), &account.info(), seeds),
+            Err(AppError::InvalidDerivedKey.into())
+        );
+    }
+
+    #[test]
+    fn assert_rent_exempt_checks_the_balance_for_the_size() {
+        let rent = Rent::default();
+        let mut account = TestAccount::new(Pubkey::new_unique(), crate::id(), vec![0; 100]);
+        account.lamports = rent.minimum_balance(100);
+        assert_eq!(assert_rent_exempt(&rent, &account.info()), Ok(()));
+        account.lamports -= 1;
+        assert_eq!(assert_rent_exempt(&rent, &account.info()), Err(AppError::NotRentExempt.into()));
+    }
+
+    #[test]
+    fn assert_initialized_and_uninitialized_read_the_flag() {
+        let key = Pubkey::new_unique();
+        let mut mint = TestAccount::mint(key, &spl_token::id(), &Pubkey::new_unique(), 0);
+        assert!(assert_initialized::<spl_token::state::Mint>(&mint.info()).is_ok());
+        assert_eq!(
+            assert_uninitialized::<spl_token::state::Mint>(&mint.info()).err(),
+            Some(AppError::AlreadyInitialized.into())
+        );
+
+        let mut blank =
+            TestAccount::new(key, spl_token::id(), vec![0; spl_token::state::Mint::LEN]);
+        assert!(assert_uninitialized::<spl_token::state::Mint>(&blank.info()).is_ok());
+        assert_eq!(
+            assert_initialized::<spl_token::state::Mint>(&blank.info()).err(),
+            Some(AppError::Uninitialized.into())
+        );
+    }
+
+    #[test]
+    fn token_account_helpers_read_both_token_programs() {
+        for program_id in token_programs() {
+            let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let mut program = TestAccount::program(program_id);
+            let mut account =
+                TestAccount::token_account(Pubkey::new_unique(), &program_id, &mint, &owner, 7);
+            let token_program = TokenProgram::from_account_info(&program.info()).unwrap();
+            let account_info = account.info();
+
+            let unpacked =
+                assert_token_account(&token_program, &account_info, &owner, &mint).unwrap();
+            assert_eq!((unpacked.mint, unpacked.owner, unpacked.amount), (mint, owner, 7));
+            assert!(!unpacked.is_frozen());
+            assert!(assert_token_account_owner(&token_program, &account_info, &owner).is_ok());
+            assert!(assert_token_account_mint(&token_program, &account_info, &mint).is_ok());
+            assert_eq!(
+                token_program.account_mint_and_owner(&account_info).unwrap(),
+                (mint, owner)
+            );
+        }
+    }
+
+    #[test]
+    fn token_account_helpers_reject_a_wrong_owner_or_mint() {
+        for program_id in token_programs() {
+            let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let mut program = TestAccount::program(program_id);
+            let mut account =
+                TestAccount::token_account(Pubkey::new_unique(), &program_id, &mint, &owner, 1);
+            let token_program = TokenProgram::from_account_info(&program.info()).unwrap();
+            let account_info = account.info();
+            let other = Pubkey::new_unique();
+
+            assert_eq!(
+                assert_token_account_owner(&token_program, &account_info, &other),
+                Err(AppError::InvalidOwner.into())
+            );
+            assert_eq!(
+                assert_token_account_mint(&token_program, &account_info, &other),
+                Err(AppError::InvalidEqPubkey.into())
+            );
+            assert_eq!(
+                assert_token_account(&token_program, &account_info, &owner, &other),
+                Err(AppError::InvalidEqPubkey.into())
+            );
+        }
+    }
+
+    #[test]
+    fn token_account_helpers_reject_an_account_of_the_other_program() {
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut legacy = TestAccount::program(spl_token::id());
+        let mut token_2022 = TestAccount::program(spl_token_2022::id());
+        let mut legacy_account =
+            TestAccount::token_account(Pubkey::new_unique(), &spl_token::id(), &mint, &owner, 1);
+        let mut account_2022 = TestAccount::token_account(
+            Pubkey::new_unique(),
+            &spl_token_2022::id(),
+            &mint,
+            &owner,
+            1,
+        );
+        let legacy = TokenProgram::from_account_info(&legacy.info()).unwrap();
+        let token_2022 = TokenProgram::from_account_info(&token_2022.info()).unwrap();
+
+        assert_eq!(
+            assert_token_account_owner(&legacy, &account_2022.info(), &owner),
+            Err(AppError::InvalidOwner.into())
+        );
+        assert_eq!(
+            assert_token_account_owner(&token_2022, &legacy_account.info(), &owner),
+            Err(AppError::InvalidOwner.into())
+        );
+    }
+
+    #[test]
+    fn token_account_helpers_reject_garbage_data() {
+        for program_id in token_programs() {
+            let mut program = TestAccount::program(program_id);
+            let mut account = TestAccount::new(Pubkey::new_unique(), program_id, vec![0; 10]);
+            let token_program = TokenProgram::from_account_info(&program.info()).unwrap();
+            assert_eq!(
+                token_program.unpack_account(&account.info()),
+                Err(AppError::InvalidTokenAccount.into())
+            );
+        }
+    }
+
+    #[test]
+    fn unpack_account_keeps_the_frozen_state_of_token_2022() {
+        let mut program = TestAccount::program(spl_token_2022::id());
+        let mut account = TestAccount::token_account(
+            Pubkey::new_unique(),
+            &spl_token_2022::id(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1,
+        );
+        let mut base = spl_token_2022::state::Account::unpack(&account.data).unwrap();
+        base.state = Token2022AccountState::Frozen;
+        spl_token_2022::state::Account::pack(base, &mut account.data).unwrap();
+
+        let token_program = TokenProgram::from_account_info(&program.info()).unwrap();
+        assert!(token_program.unpack_account(&account.info()).unwrap().is_frozen());
+    }
+
+    #[test]
+    fn random_values_differ_between_mints_in_the_same_slot() {
+        setup();
+        set_clock(100, 1_700_000_000);
+        let mut slot_hashes = TestAccount::slot_hashes([3; 32]);
+        let slot_hashes = slot_hashes.info();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let draw = |mint: &Pubkey| get_random_u64(&slot_hashes, mint.as_ref()).unwrap();
+        assert_ne!(draw(&first), draw(&second));
+        assert_eq!(draw(&first), draw(&first));
+        let range = |mint: &Pubkey| get_random_range(&slot_hashes, mint.as_ref(), 1 << 32);
+        assert_ne!(range(&first).unwrap(), range(&second).unwrap());
+    }
+
+    #[test]
+    fn random_range_stays_below_max() {
+        setup();
+        let mut slot_hashes = TestAccount::slot_hashes([3; 32]);
+        let slot_hashes = slot_hashes.info();
+        for max in [1, 2, 3, 7, 256, u64::MAX / 2 + 1, u64::MAX] {
+            for nonce in 0u8..16 {
+                assert!(get_random_range(&slot_hashes, &[nonce], max).unwrap() < max);
+            }
+        }
+        assert_eq!(
+            get_random_range(&slot_hashes, &[0], 0),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn random_range_needs_the_slot_hashes_sysvar() {
+        setup();
+        let mut fake = TestAccount::wallet(Pubkey::new_unique());
+        assert_eq!(
+            get_random_range(&fake.info(), &[0], 10),
+            Err(AppError::InvalidSlotHashesSysvar.into())
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_random_u8_rejects_divisors_outside_a_u8() {
+        setup();
+        assert_eq!(get_random_u8(1, 0), Err(ProgramError::InvalidArgument));
+        assert_eq!(get_random_u8(1, 257), Err(ProgramError::InvalidArgument));
+        assert_eq!(get_random_u8(1, 1), Ok(0));
+        for seed in 0..=u8::MAX {
+            assert!(u64::from(get_random_u8(seed, 200).unwrap()) < 200);
+            get_random_u8(seed, 256).unwrap();
+        }
+        assert_eq!(get_random(7), get_random(7));
+    }
+
+    #[test]
+    fn optional_account_treats_the_deployed_program_as_absent() {
+        let deployed = Pubkey::new_unique();
+        let mut accounts = [
+            TestAccount::program(deployed),
+            TestAccount::program(crate::id()),
+            TestAccount::wallet(Pubkey::new_unique()),
+        ];
+        let accounts = infos(&mut accounts);
+        let iter = &mut accounts.iter();
+        assert!(optional_account(&deployed, iter).unwrap().is_none());
+        assert_eq!(optional_account(&deployed, iter).unwrap().map(|a| *a.key), Some(crate::id()));
+        assert!(optional_account(&deployed, iter).unwrap().is_some());
+        assert_eq!(
+            optional_account(&deployed, iter).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+}